@@ -3,12 +3,16 @@ use ode::ParameterEstimation;
 use ode::ga_json::{save_json, GA_Argument, Bound, ConfigData, GA_Metadata};
 
 use std::env;
+use std::path::Path;
+
+const CONFIG_PATH: &str = "./src/ode/config/ga_input.json";
 
 fn main(){
     env::set_var("RUST_BACKTRACE", "1");
     
     let metadata = GA_Metadata {name: String::from("Parameter estimation"), start_time: 0.0, 
-        delta_time: 0.1, end_time: 60.0, population_size: 80, crossover_rate: 0.5, mutation_rate: 0.8, max_iterations: 50};
+        delta_time: 0.1, end_time: 60.0, population_size: 80, crossover_rate: 0.5, mutation_rate: 0.8, max_iterations: 50, 
+        ..Default::default()};
     
     let mut arguments: Vec<GA_Argument> = vec![];
     arguments.push(GA_Argument::new(String::from("N"), 1.));
@@ -19,12 +23,19 @@ fn main(){
     config_bounds.push(Bound::new(String::from("r"),0.1, 1.0));
     config_bounds.push(Bound::new(String::from("k"),1.0, 200.));
 
-    let _ = save_json(ConfigData { metadata: metadata, arguments: arguments, bounds: config_bounds}, 
-            "./src/ode/config/ga_input.json");
+    //the default configuration is written only if there is none, so the changes made to the file are kept
+    if !Path::new(CONFIG_PATH).exists() {
+        let _ = save_json(ConfigData { metadata, arguments, bounds: config_bounds }, CONFIG_PATH);
+    }
     
     let mut param_estimator: ParameterEstimation = ParameterEstimation::new( 
         String::from("./src/ode/tests/logistic_data.csv"));
-    let mut ode_system = param_estimator.ode_system("./src/ode/config/ga_input.json", "./src/ode/tests/logistic.txt");
-    param_estimator.estimate_parameters(&mut ode_system);
+    let mut ode_system = param_estimator.ode_system(CONFIG_PATH, "./src/ode/tests/logistic.txt");
+    
+    //an optional argument is the path of a checkpoint to resume
+    match env::args().nth(1) {
+        Some(checkpoint_path) => param_estimator.resume_parameter_estimation(&mut ode_system, &checkpoint_path),
+        None => param_estimator.estimate_parameters(&mut ode_system),
+    }
     
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use super::ga_json::Bound;
+use super::objective::Objective;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CacheConfig {
+    //size of the cells of the grid on which the values are compared, as a fraction of the width of each bound
+    //0 compares the exact values, otherwise an individual gets the fitness of the centre of its cell
+    pub quantization: f64,
+    pub capacity: usize, //the cache is emptied when it has this number of entries
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            quantization: 0.0,
+            capacity: 100000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        if self.hits + self.misses == 0 {
+            return 0.0;
+        }
+        self.hits as f64/(self.hits + self.misses) as f64
+    }
+}
+
+//values of the evaluated individuals by key of their genes
+type Entries<T> = Mutex<HashMap<Vec<u64>, Arc<OnceLock<T>>>>;
+
+//objective that remembers the fitness (and the objectives) of the values already evaluated,
+//it is shared by the threads of the evaluation
+pub struct Cached<O> {
+    objective: O,
+    bounds: Vec<Bound>,
+    config: Option<CacheConfig>, //None evaluates every individual
+    fitness: Entries<f64>,
+    objectives: Entries<(f64, Vec<f64>)>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl<O: Objective> Cached<O> {
+
+    pub fn new(objective: O, bounds: Vec<Bound>, config: Option<CacheConfig>) -> Self {
+        Self {
+            objective,
+            bounds,
+            config,
+            fitness: Mutex::new(HashMap::new()),
+            objectives: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> Option<CacheStats> {
+        self.config.as_ref().map(|_| CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        })
+    }
+
+    //key of the cell of `values` and the point that is evaluated for it, with quantization it is the centre of the cell
+    //so that the fitness of a cell does not depend on which of its individuals is evaluated first
+    fn cell(&self, config: &CacheConfig, values: &[f64]) -> (Vec<u64>, Vec<f64>) {
+
+        if config.quantization <= 0.0 {
+            return (values.iter().map(|v| v.to_bits()).collect(), values.to_vec());
+        }
+
+        values
+            .iter()
+            .zip(self.bounds.iter())
+            .map(|(v, b)| {
+                let width: f64 = if b.max > b.min { b.max - b.min } else { 1.0 };
+                let index: i64 = ((v - b.min)/width/config.quantization).round() as i64;
+                (index as u64, b.clamp(b.min + index as f64*config.quantization*width))
+            })
+            .unzip()
+    }
+
+    //value of the map for the cell of `values`, calculated by `evaluate` if it is not there,
+    //and the number of calculations (0 or 1)
+    //each cell is calculated once even if several threads look it up at the same time
+    fn lookup<T: Clone>(&self, map: &Entries<T>, values: &[f64], evaluate: impl FnOnce(&[f64]) -> T) -> (T, usize) {
+
+        let config: &CacheConfig = match &self.config {
+            Some(c) => c,
+            None => return (evaluate(values), 1),
+        };
+
+        let (key, point) = self.cell(config, values);
+        let entry: Arc<OnceLock<T>> = match map.lock() {
+            Ok(mut m) => {
+                if m.len() >= config.capacity && !m.contains_key(&key) {
+                    m.clear();
+                }
+                m.entry(key).or_default().clone()
+            },
+            Err(_) => return (evaluate(&point), 1),
+        };
+
+        let mut evaluations: usize = 0;
+        let value: T = entry.get_or_init(|| { evaluations = 1; evaluate(&point) }).clone();
+        if evaluations == 0 {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        (value, evaluations)
+    }
+}
+
+impl<O: Objective> Objective for Cached<O> {
+    type Worker = O::Worker;
+
+    fn worker(&self) -> O::Worker {
+        self.objective.worker()
+    }
+
+    fn evaluate(&self, worker: &mut O::Worker, values: &[f64]) -> f64 {
+        self.evaluate_counted(worker, values).0
+    }
+
+    //the residuals are not cached, Levenberg-Marquardt evaluates points that are rarely repeated
+    fn residuals(&self, worker: &mut O::Worker, values: &[f64]) -> Option<Vec<f64>> {
+        self.objective.residuals(worker, values)
+    }
+
+    fn objectives(&self, worker: &mut O::Worker, values: &[f64]) -> (f64, Vec<f64>) {
+        self.objectives_counted(worker, values).0
+    }
+
+    //the values found in the cache do not count as evaluations
+    fn evaluate_counted(&self, worker: &mut O::Worker, values: &[f64]) -> (f64, usize) {
+        self.lookup(&self.fitness, values, |point| self.objective.evaluate(worker, point))
+    }
+
+    fn objectives_counted(&self, worker: &mut O::Worker, values: &[f64]) -> ((f64, Vec<f64>), usize) {
+        self.lookup(&self.objectives, values, |point| self.objective.objectives(worker, point))
+    }
+
+    //the violation is cheap and not cached, it is calculated at the point whose fitness is used
+    fn violation(&self, worker: &mut O::Worker, values: &[f64]) -> f64 {
+        match &self.config {
+            Some(config) => self.objective.violation(worker, &self.cell(config, values).1),
+            None => self.objective.violation(worker, values),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Squares = (fn(), fn(&mut (), &[f64]) -> f64);
+
+    fn cached(config: Option<CacheConfig>) -> Cached<Squares> {
+        let objective: Squares = (|| (), |_, values| values.iter().map(|v| v*v).sum::<f64>());
+        Cached::new(objective, vec![Bound::new(String::from("x"), 0.0, 1.0)], config)
+    }
+
+    #[test]
+    fn repeated_values_are_hits() {
+        let cache = cached(Some(CacheConfig::default()));
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.5]), (0.25, 1));
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.5]), (0.25, 0));
+        assert_eq!(cache.stats(), Some(CacheStats { hits: 1, misses: 1 }));
+    }
+
+    #[test]
+    fn different_values_are_misses() {
+        let cache = cached(Some(CacheConfig::default()));
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.5]), (0.25, 1));
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.25]), (0.0625, 1));
+        assert_eq!(cache.stats(), Some(CacheStats { hits: 0, misses: 2 }));
+    }
+
+    #[test]
+    fn quantized_values_get_the_fitness_of_the_centre_of_the_cell() {
+        let cache = cached(Some(CacheConfig { quantization: 0.1, ..Default::default() }));
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.52]).1, 1);
+        let (fitness, evaluations) = cache.evaluate_counted(&mut (), &[0.48]);
+        assert!((fitness - 0.25).abs() < 1.0e-12);
+        assert_eq!(evaluations, 0);
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.56]).1, 1);
+    }
+
+    #[test]
+    fn full_cache_is_emptied() {
+        let cache = cached(Some(CacheConfig { capacity: 2, ..Default::default() }));
+        for x in [0.1, 0.2, 0.3] {
+            assert_eq!(cache.evaluate_counted(&mut (), &[x]).1, 1);
+        }
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.3]).1, 0);
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.1]).1, 1);
+    }
+
+    #[test]
+    fn no_configuration_evaluates_every_time() {
+        let cache = cached(None);
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.5]).1, 1);
+        assert_eq!(cache.evaluate_counted(&mut (), &[0.5]).1, 1);
+        assert_eq!(cache.stats(), None);
+    }
+}
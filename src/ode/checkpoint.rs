@@ -0,0 +1,121 @@
+use std::{fs::{self, File}, io::{BufReader, BufWriter, Error}, path::Path};
+
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::ga::Chromosome;
+use super::ga_json::ConfigData;
+use super::history::History;
+
+//where and how often (in generations) the state of the GA is saved
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CheckpointConfig {
+    pub path: String,
+    pub interval: usize,
+}
+
+//full state of a GA run at the end of a generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub config_hash: u64,
+    pub generation: usize,
+    pub evaluations: usize,
+    pub elapsed: f64, //seconds
+    pub generations_without_improvement: usize,
+    pub best: Chromosome,
+    pub population: Vec<Chromosome>,
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+    #[serde(default)]
+    pub history: History,
+    #[serde(default)]
+    pub hall_of_fame: Vec<Chromosome>,
+}
+
+//FNV-1a hash of the configuration
+//the stopping rules, the number of threads and the output files may change when a run is resumed
+pub fn config_hash(config: &ConfigData) -> u64 {
+
+    let mut config: ConfigData = config.clone();
+    config.metadata.max_iterations = 0;
+    config.metadata.termination = Default::default();
+    config.metadata.threads = None;
+    config.metadata.checkpoint = None;
+    config.metadata.history_file = None;
+
+    let text: String = serde_json::to_string(&config).unwrap_or_default();
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+//the checkpoint is written to a temporary file first, so that a crash while saving does not destroy the previous one
+pub fn save_checkpoint<P: AsRef<Path>>(checkpoint: &Checkpoint, path: P) -> anyhow::Result<(),Error> {
+
+    let temp_path = path.as_ref().with_extension("tmp");
+
+    let file: File = File::create(&temp_path)?;
+    let writer: BufWriter<File> = BufWriter::new(file);
+    serde_json::to_writer(writer, checkpoint)?;
+
+    fs::rename(temp_path, path)
+}
+
+pub fn load_checkpoint<P: AsRef<Path>>(path: P) -> anyhow::Result<Checkpoint,Error> {
+
+    let file: File = File::open(path)?;
+    let reader: BufReader<File> = BufReader::new(file);
+
+    Ok(serde_json::from_reader(reader)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ga::GA;
+    use super::super::ga_json::{Bound, GA_Metadata};
+    use super::super::mutation::Mutation;
+    use super::super::observer::ConsoleObserver;
+    use super::super::optimizer::{OptimizationResult, Optimizer};
+
+    fn metadata(max_iterations: usize, checkpoint: Option<CheckpointConfig>) -> GA_Metadata {
+        GA_Metadata {
+            population_size: 60, crossover_rate: 0.5, mutation_rate: 0.5, max_iterations, seed: Some(11), threads: Some(1),
+            mutation: Mutation::SelfAdaptive { initial_step: 0.3 }, checkpoint,
+            ..Default::default()
+        }
+    }
+
+    type Rosenbrock = (fn(), fn(&mut (), &[f64]) -> f64);
+
+    fn run(metadata: &GA_Metadata, checkpoint: Option<Checkpoint>) -> OptimizationResult {
+        let bounds: Vec<Bound> = vec![Bound::new(String::from("x"), -5.0, 5.0), Bound::new(String::from("y"), -5.0, 5.0)];
+        let objective: Rosenbrock = (|| (), |_, values| (1.0 - values[0]).powi(2) + 100.0*(values[1] - values[0]*values[0]).powi(2));
+        let mut ga: GA = GA::from_metadata(metadata, bounds, true);
+        if let Some(c) = checkpoint {
+            assert!(Optimizer::<Rosenbrock>::resume(&mut ga, c));
+        }
+        ga.optimize(&objective, &mut ConsoleObserver).unwrap()
+    }
+
+    #[test]
+    fn resumed_run_equals_the_uninterrupted_one() {
+        let path: String = std::env::temp_dir().join(format!("ga_test_checkpoint_{}.json", std::process::id())).to_string_lossy().into_owned();
+
+        let uninterrupted: OptimizationResult = run(&metadata(40, None), None);
+        run(&metadata(20, Some(CheckpointConfig { path: path.clone(), interval: 20 })), None);
+        let checkpoint: Checkpoint = load_checkpoint(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(checkpoint.generation, 20);
+
+        let resumed: OptimizationResult = run(&metadata(40, None), Some(checkpoint));
+        assert_eq!(resumed.best.values(), uninterrupted.best.values());
+        assert_eq!(resumed.best.fitness.to_bits(), uninterrupted.best.fitness.to_bits());
+        assert_eq!(resumed.evaluations, uninterrupted.evaluations);
+        assert_eq!(resumed.generations, uninterrupted.generations);
+    }
+}
@@ -0,0 +1,374 @@
+use std::time::Instant;
+
+use nalgebra::{DMatrix, DVector, SymmetricEigen};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{fitness_order, is_fitter, Chromosome};
+use super::ga_json::{Bound, GA_Metadata};
+use super::hall_of_fame::HallOfFameConfig;
+use super::history::History;
+use super::mutation::standard_normal;
+use super::objective::{evaluate_population, thread_pool, Objective};
+use super::observer::Observer;
+use super::optimizer::{run_seed, OptimizationResult, Optimizer, RunTracker};
+use super::termination::{StopReason, Termination};
+
+//what is done when a run of the CMA-ES converges or stagnates
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Restarts {
+    //the search goes on with the converged distribution
+    None,
+    //every restart multiplies the population size
+    #[default]
+    Ipop,
+    //alternates between increasing population sizes and small populations with small step sizes
+    Bipop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CmaesParameters {
+    pub sigma: f64, //initial step size as a fraction of the width of the bounds
+    pub lambda: Option<usize>, //offspring per iteration, None uses 4 + 3 ln(n)
+    pub restarts: Restarts,
+    pub increase: f64, //factor applied to the population size by each large restart
+    pub max_restarts: Option<usize>,
+}
+
+impl Default for CmaesParameters {
+    fn default() -> Self {
+        Self {
+            sigma: 0.3,
+            lambda: None,
+            restarts: Restarts::default(),
+            increase: 2.0,
+            max_restarts: None,
+        }
+    }
+}
+
+//state of a single run, the search is done in the unit box and mapped to the bounds on evaluation
+#[derive(Debug, Clone)]
+struct Distribution {
+    n: usize,
+    lambda: usize,
+    weights: Vec<f64>,
+    mueff: f64,
+    cc: f64,
+    cs: f64,
+    c1: f64,
+    cmu: f64,
+    damps: f64,
+    chi_n: f64,
+    mean: DVector<f64>,
+    sigma: f64,
+    c: DMatrix<f64>,
+    b: DMatrix<f64>,
+    d: DVector<f64>,
+    pc: DVector<f64>,
+    ps: DVector<f64>,
+    iteration: usize,
+    best: Vec<f64>, //best fitness of each iteration of the run
+}
+
+impl Distribution {
+
+    fn new(mean: DVector<f64>, sigma: f64, lambda: usize) -> Self {
+
+        let n: usize = mean.len();
+        let nf: f64 = n as f64;
+        let mu: usize = (lambda/2).max(1);
+
+        let raw: Vec<f64> = (1..=mu).map(|i| (mu as f64 + 0.5).ln() - (i as f64).ln()).collect();
+        let total: f64 = raw.iter().sum();
+        let weights: Vec<f64> = raw.iter().map(|w| w/total).collect();
+        let mueff: f64 = 1.0/weights.iter().map(|w| w*w).sum::<f64>();
+
+        let cc: f64 = (4.0 + mueff/nf)/(nf + 4.0 + 2.0*mueff/nf);
+        let cs: f64 = (mueff + 2.0)/(nf + mueff + 5.0);
+        let c1: f64 = 2.0/((nf + 1.3).powi(2) + mueff);
+        let cmu: f64 = (1.0 - c1).min(2.0*(mueff - 2.0 + 1.0/mueff)/((nf + 2.0).powi(2) + mueff));
+        let damps: f64 = 1.0 + 2.0*(((mueff - 1.0)/(nf + 1.0)).sqrt() - 1.0).max(0.0) + cs;
+
+        Self {
+            n,
+            lambda,
+            weights,
+            mueff,
+            cc,
+            cs,
+            c1,
+            cmu,
+            damps,
+            chi_n: nf.sqrt()*(1.0 - 1.0/(4.0*nf) + 1.0/(21.0*nf*nf)),
+            mean,
+            sigma,
+            c: DMatrix::identity(n, n),
+            b: DMatrix::identity(n, n),
+            d: DVector::from_element(n, 1.0),
+            pc: DVector::zeros(n),
+            ps: DVector::zeros(n),
+            iteration: 0,
+            best: vec![],
+        }
+    }
+
+    //a point of the unit box, resampled a few times before being clamped
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DVector<f64> {
+
+        let mut x: DVector<f64> = DVector::zeros(self.n);
+        for _ in 0..10 {
+            let z: DVector<f64> = DVector::from_fn(self.n, |_, _| standard_normal(rng));
+            x = &self.mean + self.sigma*(&self.b*self.d.component_mul(&z));
+            if x.iter().all(|v| (0.0..=1.0).contains(v)) {
+                return x;
+            }
+        }
+        x.map(|v| v.clamp(0.0, 1.0))
+    }
+
+    //moves the distribution towards the best points, `ranked` is sorted from best to worst
+    fn update(&mut self, ranked: &[&DVector<f64>], best_fitness: f64) {
+
+        self.iteration += 1;
+        self.best.push(best_fitness);
+
+        let old_mean: DVector<f64> = self.mean.clone();
+        self.mean = ranked.iter()
+            .zip(self.weights.iter())
+            .fold(DVector::zeros(self.n), |m, (x, w)| m + *w*(*x));
+
+        let step: DVector<f64> = (&self.mean - &old_mean)/self.sigma;
+        let inv_sqrt_c: DMatrix<f64> = &self.b*DMatrix::from_diagonal(&self.d.map(|d| 1.0/d))*self.b.transpose();
+
+        self.ps = (1.0 - self.cs)*&self.ps + (self.cs*(2.0 - self.cs)*self.mueff).sqrt()*(&inv_sqrt_c*&step);
+
+        let ps_norm: f64 = self.ps.norm()/(1.0 - (1.0 - self.cs).powi(2*self.iteration as i32)).sqrt();
+        let hsig: f64 = if ps_norm/self.chi_n < 1.4 + 2.0/(self.n as f64 + 1.0) { 1.0 } else { 0.0 };
+
+        self.pc = (1.0 - self.cc)*&self.pc + hsig*(self.cc*(2.0 - self.cc)*self.mueff).sqrt()*&step;
+
+        let rank_mu: DMatrix<f64> = ranked.iter()
+            .zip(self.weights.iter())
+            .fold(DMatrix::zeros(self.n, self.n), |acc, (x, w)| {
+                let y: DVector<f64> = (*x - &old_mean)/self.sigma;
+                acc + *w*&y*y.transpose()
+            });
+
+        self.c = (1.0 - self.c1 - self.cmu)*&self.c
+            + self.c1*(&self.pc*self.pc.transpose() + (1.0 - hsig)*self.cc*(2.0 - self.cc)*&self.c)
+            + self.cmu*rank_mu;
+
+        self.sigma *= ((self.cs/self.damps)*(self.ps.norm()/self.chi_n - 1.0)).exp();
+
+        //the matrix is kept symmetric to avoid the accumulation of rounding errors
+        self.c = 0.5*(&self.c + self.c.transpose());
+        let eigen: SymmetricEigen<f64, nalgebra::Dyn> = SymmetricEigen::new(self.c.clone());
+        self.b = eigen.eigenvectors;
+        self.d = eigen.eigenvalues.map(|e| e.max(1e-20).sqrt());
+    }
+
+    //true when the run converged, the covariance degenerated or the best fitness stagnated
+    fn finished(&self) -> bool {
+
+        if self.sigma*self.d.max() < 1e-12 {
+            return true;
+        }
+        if self.d.max()/self.d.min() > 1e7 {
+            return true;
+        }
+
+        let window: usize = 10 + (30*self.n).div_ceil(self.lambda);
+        if self.best.len() > window {
+            let recent: &[f64] = &self.best[self.best.len() - window..];
+            let max: f64 = recent.iter().cloned().fold(f64::MIN, f64::max);
+            let min: f64 = recent.iter().cloned().fold(f64::MAX, f64::min);
+            if max - min < 1e-12 {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cmaes {
+    parameters: CmaesParameters,
+    bounds: Vec<Bound>,
+    minimization: bool,
+    max_iterations: usize,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    threads: Option<usize>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    history: History,
+}
+
+impl Cmaes {
+
+    pub fn from_metadata(metadata: &GA_Metadata, parameters: CmaesParameters, bounds: Vec<Bound>, is_min: bool) -> Self {
+        let seed: u64 = run_seed(metadata);
+        Self {
+            parameters,
+            bounds,
+            minimization: is_min,
+            max_iterations: metadata.max_iterations,
+            termination: metadata.termination.clone(),
+            hall_of_fame: metadata.hall_of_fame.clone(),
+            threads: metadata.threads,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            history: History::default(),
+        }
+    }
+
+    fn to_bounds(&self, u: &DVector<f64>) -> Vec<f64> {
+        self.bounds.iter().zip(u.iter()).map(|(b, u)| b.min + u*(b.max - b.min)).collect()
+    }
+
+    //covariance of the parameters: the covariance of the distribution scaled by the widths of the bounds
+    fn covariance(&self, distribution: &Distribution) -> DMatrix<f64> {
+        let widths: DVector<f64> = DVector::from_iterator(self.bounds.len(), self.bounds.iter().map(|b| b.max - b.min));
+        let scale: DMatrix<f64> = DMatrix::from_diagonal(&widths);
+        distribution.sigma.powi(2)*&scale*&distribution.c*&scale
+    }
+
+    fn new_distribution(&mut self, lambda: usize, sigma: f64) -> Distribution {
+        let n: usize = self.bounds.len();
+        let mean: DVector<f64> = DVector::from_fn(n, |_, _| self.rng.gen_range(0.0..1.0));
+        Distribution::new(mean, sigma, lambda)
+    }
+}
+
+impl<O: Objective> Optimizer<O> for Cmaes {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> {
+
+        let start: Instant = Instant::now();
+
+        let pool: ThreadPool = thread_pool(self.threads)?;
+
+        let n: usize = self.bounds.len();
+        if n == 0 {
+            println!("CMA-ES needs at least one bound");
+            return Err(());
+        }
+
+        let default_lambda: usize = self.parameters.lambda.unwrap_or(4 + (3.0*(n as f64).ln()).floor() as usize).max(2);
+        let default_sigma: f64 = self.parameters.sigma;
+
+        //bookkeeping of the restarts, the budgets are the evaluations spent in each BIPOP regime
+        let mut restarts: usize = 0;
+        let mut large_lambda: usize = default_lambda;
+        let mut large_budget: usize = 0;
+        let mut small_budget: usize = 0;
+        let mut small_run: bool = false;
+
+        let mut distribution: Distribution = self.new_distribution(default_lambda, default_sigma);
+        let mut tracker: Option<RunTracker> = None;
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(t) = tracker.as_mut() {
+                if let Some(reason) = t.next_iteration(&self.termination, self.max_iterations, observer) {
+                    break reason;
+                }
+            }
+
+            let points: Vec<DVector<f64>> = (0..distribution.lambda).map(|_| distribution.sample(&mut self.rng)).collect();
+            let mut population: Vec<Chromosome> = points.iter().map(|u| Chromosome::new(self.to_bounds(u))).collect();
+            let evaluations: usize = evaluate_population(&pool, &mut population, objective);
+
+            if small_run { small_budget += evaluations } else { large_budget += evaluations }
+
+            let mut order: Vec<usize> = (0..population.len()).collect();
+            order.sort_by(|a, b| fitness_order(&population[*a], &population[*b], self.minimization));
+            let ranked: Vec<&DVector<f64>> = order.iter().take(distribution.weights.len()).map(|i| &points[*i]).collect();
+            distribution.update(&ranked, population[order[0]].fitness);
+
+            //the first sampling plays the role of the initial population
+            let t: &mut RunTracker = match tracker.as_mut() {
+                Some(t) => {
+                    t.evaluations += evaluations;
+                    t.end_iteration(&population, &self.bounds, observer);
+                    t
+                },
+                None => tracker.insert(RunTracker::new(start, &population, &self.bounds, evaluations, self.minimization, self.hall_of_fame.as_ref(), observer)?),
+            };
+
+            //the reported covariance belongs to the run that found the best solution
+            if !is_fitter(&t.best, &population[order[0]], self.minimization) {
+                t.covariance = Some(self.covariance(&distribution));
+            }
+
+            if self.parameters.restarts == Restarts::None || !distribution.finished() {
+                continue;
+            }
+            if self.parameters.max_restarts.is_some_and(|max| restarts >= max) {
+                continue;
+            }
+            restarts += 1;
+
+            let (lambda, sigma) = match self.parameters.restarts {
+                Restarts::Bipop if large_budget > small_budget => {
+                    small_run = true;
+                    let u: f64 = self.rng.gen_range(0.0..1.0);
+                    //between the default size and half the size of the next large population
+                    let ratio: f64 = 0.5*self.parameters.increase*large_lambda as f64/default_lambda as f64;
+                    let lambda: usize = (default_lambda as f64*ratio.powf(u*u)).floor() as usize;
+                    (lambda.max(2), default_sigma*10f64.powf(-2.0*u))
+                },
+                _ => {
+                    small_run = false;
+                    large_lambda = (large_lambda as f64*self.parameters.increase).round() as usize;
+                    (large_lambda, default_sigma)
+                },
+            };
+            distribution = self.new_distribution(lambda, sigma);
+        };
+
+        let tracker: RunTracker = tracker.ok_or(())?;
+        let result: OptimizationResult = tracker.finish(stop_reason, observer);
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::observer::ConsoleObserver;
+
+    type Rosenbrock = (fn(), fn(&mut (), &[f64]) -> f64);
+
+    #[test]
+    fn converges_to_the_minimum_of_the_rosenbrock_function() {
+        let bounds: Vec<Bound> = vec![Bound::new(String::from("x"), -5.0, 5.0), Bound::new(String::from("y"), -5.0, 5.0)];
+        let objective: Rosenbrock = (|| (), |_, values| (1.0 - values[0]).powi(2) + 100.0*(values[1] - values[0]*values[0]).powi(2));
+        let metadata: GA_Metadata = GA_Metadata { max_iterations: 300, seed: Some(1), threads: Some(1), ..Default::default() };
+
+        let result: OptimizationResult = Cmaes::from_metadata(&metadata, CmaesParameters::default(), bounds, true)
+            .optimize(&objective, &mut ConsoleObserver)
+            .unwrap();
+        assert!(result.best.fitness < 1e-8);
+        assert!((result.best.values()[0] - 1.0).abs() < 1e-3 && (result.best.values()[1] - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,473 @@
+use mexprp::{Answer, Context, Expression};
+use serde::{Deserialize, Serialize};
+
+use super::ga::Chromosome;
+use super::ga_json::{Bound, GA_Argument, Kind};
+use super::objective::Objective;
+use super::optimizer::OptimizationResult;
+
+//what is done with the individuals that violate the constraints
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintHandling {
+    //the violation multiplied by the penalty coefficient makes the fitness worse
+    #[default]
+    Penalty,
+    //Deb's rules: a feasible individual is better than an infeasible one, and between infeasible ones
+    //the smaller violation wins, the fitness is not modified and the violation is compared before it (see is_fitter)
+    FeasibilityRules,
+    //the parameters are moved towards the feasible region before the evaluation and the reporting,
+    //the individuals that can not be repaired are handled by the feasibility rules
+    Repair,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Constraints {
+    //relations between expressions of the parameters and arguments, e.g. "delta_the > delta_thm", "beta*N/alpha <= 5"
+    //the operators are <=, <, >=, >, == and =, the strict ones are handled as the non-strict ones
+    pub expressions: Vec<String>,
+    pub handling: ConstraintHandling,
+    pub penalty: f64, //coefficient of the penalty
+    pub tolerance: f64, //violation accepted in the equalities
+    pub repair_iterations: usize,
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Self {
+            expressions: vec![],
+            handling: ConstraintHandling::default(),
+            penalty: 1e3,
+            tolerance: 1e-6,
+            repair_iterations: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Relation {
+    LessEqual,
+    GreaterEqual,
+    Equal,
+}
+
+//a constraint written as a relation between the difference of its sides and zero
+#[derive(Debug, Clone)]
+struct Constraint {
+    text: String,
+    difference: String,
+    relation: Relation,
+}
+
+impl Constraint {
+
+    fn parse(text: &str) -> Result<Self,String> {
+
+        let operators: [(&str, Relation); 6] = [
+            ("<=", Relation::LessEqual), (">=", Relation::GreaterEqual), ("==", Relation::Equal),
+            ("<", Relation::LessEqual), (">", Relation::GreaterEqual), ("=", Relation::Equal),
+        ];
+
+        let (position, operator, relation) = operators
+            .iter()
+            .find_map(|(op, relation)| text.find(op).map(|p| (p, *op, *relation)))
+            .ok_or_else(|| format!("the constraint {} has no relational operator", text))?;
+
+        let (lhs, rhs) = (text[..position].trim(), text[position + operator.len()..].trim());
+        if lhs.is_empty() || rhs.is_empty() || rhs.contains(['<', '>', '=']) {
+            return Err(format!("the constraint {} must have one expression at each side of the operator", text));
+        }
+
+        let difference: String = format!("({}) - ({})", lhs, rhs);
+        if let Err(e) = Expression::<f64>::parse(&difference) {
+            return Err(format!("the constraint {} could not be parsed: {:?}", text, e));
+        }
+
+        Ok(Self {
+            text: text.to_string(),
+            difference,
+            relation,
+        })
+    }
+
+    fn violation(&self, difference: f64, tolerance: f64) -> f64 {
+        match self.relation {
+            Relation::LessEqual => difference.max(0.0),
+            Relation::GreaterEqual => (-difference).max(0.0),
+            Relation::Equal => (difference.abs() - tolerance).max(0.0),
+        }
+    }
+}
+
+//expressions of the constraints parsed by a thread, the mexprp contexts can not be shared between threads
+pub struct ConstraintWorker {
+    expressions: Vec<Expression<f64>>,
+    context: Context<f64>,
+}
+
+//constraints of a configuration, evaluated with the natural values of the parameters
+#[derive(Debug, Clone)]
+pub struct ConstraintSet {
+    config: Constraints,
+    constraints: Vec<Constraint>,
+    arguments: Vec<GA_Argument>,
+    bounds: Vec<Bound>,
+    minimization: bool,
+}
+
+impl ConstraintSet {
+
+    pub fn new(config: &Constraints, arguments: &[GA_Argument], bounds: &[Bound], minimization: bool) -> Result<Self,()> {
+
+        let mut constraints: Vec<Constraint> = vec![];
+        for text in config.expressions.iter() {
+            match Constraint::parse(text) {
+                Ok(c) => constraints.push(c),
+                Err(e) => { println!("Invalid constraint: {}", e); return Err(()); },
+            }
+        }
+
+        Ok(Self {
+            config: config.clone(),
+            constraints,
+            arguments: arguments.to_vec(),
+            bounds: bounds.to_vec(),
+            minimization,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    pub fn worker(&self) -> ConstraintWorker {
+
+        let mut context: Context<f64> = Context::new();
+        for arg in self.arguments.iter() {
+            context.set_var(arg.name.trim(), arg.value);
+        }
+
+        ConstraintWorker {
+            expressions: self.constraints.iter().filter_map(|c| Expression::parse(&c.difference).ok()).collect(),
+            context,
+        }
+    }
+
+    //violation of each constraint, infinite if its expression can not be evaluated
+    pub fn violations(&self, worker: &mut ConstraintWorker, values: &[f64]) -> Vec<f64> {
+
+        for (bound, value) in self.bounds.iter().zip(values.iter()) {
+            worker.context.set_var(&bound.name, *value);
+        }
+
+        self.constraints
+            .iter()
+            .zip(worker.expressions.iter())
+            .map(|(c, e)| match e.eval_ctx(&worker.context) {
+                Ok(Answer::Single(d)) if d.is_finite() => c.violation(d, self.config.tolerance),
+                _ => f64::INFINITY,
+            })
+            .collect()
+    }
+
+    pub fn violation(&self, worker: &mut ConstraintWorker, values: &[f64]) -> f64 {
+        self.violations(worker, values).iter().fold(0.0, |s, v| s + v)
+    }
+
+    //Newton steps along the gradient of the total violation (forward differences), kept inside the bounds
+    //the integer parameters are rounded in the direction of the step, so that a step to the boundary is not undone
+    pub fn repair(&self, worker: &mut ConstraintWorker, values: &[f64]) -> Vec<f64> {
+
+        let mut x: Vec<f64> = values.to_vec();
+
+        for _ in 0..self.config.repair_iterations {
+
+            let v: f64 = self.violation(worker, &x);
+            if v <= 0.0 || !v.is_finite() {
+                break;
+            }
+
+            let mut gradient: Vec<f64> = vec![0.0; x.len()];
+            for (k, bound) in self.bounds.iter().enumerate() {
+                let h: f64 = f64::EPSILON.sqrt()*x[k].abs().max(1e-3*(bound.max - bound.min)).max(f64::EPSILON);
+                let h: f64 = if x[k] + h <= bound.max { h } else { -h };
+                let mut step: Vec<f64> = x.clone();
+                step[k] += h;
+                gradient[k] = (self.violation(worker, &step) - v)/h;
+            }
+
+            let norm: f64 = gradient.iter().map(|g| g*g).sum();
+            if norm <= 0.0 || !norm.is_finite() {
+                break;
+            }
+
+            x = x.iter()
+                .zip(gradient.iter())
+                .zip(self.bounds.iter())
+                .map(|((x, g), b)| {
+                    let step: f64 = x - v*g/norm;
+                    match b.kind {
+                        Kind::Integer if step < *x => b.restrict(step.floor()),
+                        Kind::Integer => b.restrict(step.ceil()),
+                        Kind::Continuous => b.restrict(step),
+                    }
+                })
+                .collect();
+        }
+        x
+    }
+
+    //value of the fitness (or of an objective) with the penalty applied, the feasibility rules do not modify it
+    pub fn handle(&self, value: f64, violation: f64) -> f64 {
+
+        if violation <= 0.0 || !self.is_penalty() {
+            return value;
+        }
+
+        let sign: f64 = if self.minimization { 1.0 } else { -1.0 };
+        value + sign*self.config.penalty*violation
+    }
+
+    pub fn is_penalty(&self) -> bool {
+        self.config.handling == ConstraintHandling::Penalty
+    }
+
+    //parameters evaluated for `values`, the repaired ones with the repair handling
+    pub fn point(&self, worker: &mut ConstraintWorker, values: &[f64]) -> Vec<f64> {
+        match self.config.handling {
+            ConstraintHandling::Repair => self.repair(worker, values),
+            _ => values.to_vec(),
+        }
+    }
+
+    //result with the repaired parameters (natural values) of the best individual, the Pareto front, the hall of fame and the niches
+    pub fn repair_result(&self, result: &OptimizationResult) -> OptimizationResult {
+
+        if self.config.handling != ConstraintHandling::Repair || self.is_empty() {
+            return result.clone();
+        }
+
+        let mut worker: ConstraintWorker = self.worker();
+        let mut repaired: OptimizationResult = result.clone();
+        repaired.best = result.best.with_values(self.repair(&mut worker, result.best.values()));
+        for solution in repaired.pareto_front.iter_mut() {
+            solution.chromosome = solution.chromosome.with_values(self.repair(&mut worker, solution.chromosome.values()));
+        }
+        for member in repaired.hall_of_fame.iter_mut() {
+            *member = member.with_values(self.repair(&mut worker, member.values()));
+        }
+        for niche in repaired.niches.iter_mut() {
+            niche.best = niche.best.with_values(self.repair(&mut worker, niche.best.values()));
+        }
+        repaired
+    }
+
+    //text and violation of each constraint for an individual
+    pub fn report(&self, individual: &Chromosome) -> Vec<(String, f64)> {
+        let mut worker: ConstraintWorker = self.worker();
+        self.constraints
+            .iter()
+            .map(|c| c.text.clone())
+            .zip(self.violations(&mut worker, individual.values()))
+            .collect()
+    }
+}
+
+//objective evaluated with the natural values of the parameters, with the constraint handling applied
+//the values only depend on the parameters, so they can be cached
+pub struct Constrained<O> {
+    objective: O,
+    constraints: ConstraintSet,
+}
+
+impl<O: Objective> Constrained<O> {
+
+    pub fn new(objective: O, constraints: ConstraintSet) -> Self {
+        Self {
+            objective,
+            constraints,
+        }
+    }
+}
+
+impl<O: Objective> Objective for Constrained<O> {
+    type Worker = (O::Worker, ConstraintWorker);
+
+    fn worker(&self) -> Self::Worker {
+        (self.objective.worker(), self.constraints.worker())
+    }
+
+    fn evaluate(&self, worker: &mut Self::Worker, values: &[f64]) -> f64 {
+
+        if self.constraints.is_empty() {
+            return self.objective.evaluate(&mut worker.0, values);
+        }
+
+        let x: Vec<f64> = self.constraints.point(&mut worker.1, values);
+        let violation: f64 = self.constraints.violation(&mut worker.1, &x);
+        self.constraints.handle(self.objective.evaluate(&mut worker.0, &x), violation)
+    }
+
+    //the violation multiplied by the root of the penalty coefficient is an additional residual
+    fn residuals(&self, worker: &mut Self::Worker, values: &[f64]) -> Option<Vec<f64>> {
+
+        if self.constraints.is_empty() {
+            return self.objective.residuals(&mut worker.0, values);
+        }
+
+        let x: Vec<f64> = self.constraints.point(&mut worker.1, values);
+        let violation: f64 = self.constraints.violation(&mut worker.1, &x);
+        let mut residuals: Vec<f64> = self.objective.residuals(&mut worker.0, &x)?;
+        residuals.push(self.constraints.config.penalty.sqrt()*violation);
+        Some(residuals)
+    }
+
+    fn objectives(&self, worker: &mut Self::Worker, values: &[f64]) -> (f64, Vec<f64>) {
+
+        if self.constraints.is_empty() {
+            return self.objective.objectives(&mut worker.0, values);
+        }
+
+        let x: Vec<f64> = self.constraints.point(&mut worker.1, values);
+        let violation: f64 = self.constraints.violation(&mut worker.1, &x);
+        let (fitness, objectives) = self.objective.objectives(&mut worker.0, &x);
+        (
+            self.constraints.handle(fitness, violation),
+            objectives.into_iter().map(|o| self.constraints.handle(o, violation)).collect(),
+        )
+    }
+
+    //the penalty is already in the fitness
+    fn violation(&self, worker: &mut Self::Worker, values: &[f64]) -> f64 {
+
+        if self.constraints.is_empty() || self.constraints.is_penalty() {
+            return 0.0;
+        }
+
+        let x: Vec<f64> = self.constraints.point(&mut worker.1, values);
+        self.constraints.violation(&mut worker.1, &x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ga::sort_population;
+
+    fn bounds() -> Vec<Bound> {
+        vec![Bound::new(String::from("a"), 0.0, 10.0), Bound::new(String::from("b"), 0.0, 10.0)]
+    }
+
+    fn set(expressions: &[&str], handling: ConstraintHandling, bounds: &[Bound]) -> ConstraintSet {
+        let config: Constraints = Constraints {
+            expressions: expressions.iter().map(|e| e.to_string()).collect(),
+            handling,
+            ..Default::default()
+        };
+        ConstraintSet::new(&config, &[GA_Argument::new(String::from("n"), 2.0)], bounds, true).unwrap()
+    }
+
+    #[test]
+    fn parse_prefers_the_longest_operator() {
+        let c = Constraint::parse("a <= b").unwrap();
+        assert_eq!((c.difference.as_str(), c.relation), ("(a) - (b)", Relation::LessEqual));
+        assert_eq!(Constraint::parse("a >= b").unwrap().relation, Relation::GreaterEqual);
+        assert_eq!(Constraint::parse("a == b").unwrap().relation, Relation::Equal);
+        assert_eq!(Constraint::parse("a < b").unwrap().relation, Relation::LessEqual);
+        assert_eq!(Constraint::parse("a > b").unwrap().relation, Relation::GreaterEqual);
+        assert_eq!(Constraint::parse("a = b").unwrap().relation, Relation::Equal);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_constraints() {
+        assert!(Constraint::parse("a + b").is_err());
+        assert!(Constraint::parse("<= b").is_err());
+        assert!(Constraint::parse("a >=").is_err());
+        assert!(Constraint::parse("a < b < 3").is_err());
+        assert!(Constraint::parse("a*(b <= 2").is_err());
+    }
+
+    #[test]
+    fn violation_of_each_relation() {
+        let less: Constraint = Constraint::parse("a <= b").unwrap();
+        assert_eq!(less.violation(-1.0, 0.0), 0.0);
+        assert_eq!(less.violation(2.0, 0.0), 2.0);
+
+        let greater: Constraint = Constraint::parse("a >= b").unwrap();
+        assert_eq!(greater.violation(1.0, 0.0), 0.0);
+        assert_eq!(greater.violation(-2.0, 0.0), 2.0);
+
+        let equal: Constraint = Constraint::parse("a == b").unwrap();
+        assert_eq!(equal.violation(0.05, 0.1), 0.0);
+        assert_eq!(equal.violation(-0.05, 0.1), 0.0);
+        assert!((equal.violation(-0.5, 0.1) - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn violations_use_the_parameters_and_arguments() {
+        let constraints: ConstraintSet = set(&["a + b <= n*3", "a >= b"], ConstraintHandling::Penalty, &bounds());
+        let mut worker: ConstraintWorker = constraints.worker();
+        assert_eq!(constraints.violations(&mut worker, &[5.0, 4.0]), vec![3.0, 0.0]);
+        assert_eq!(constraints.violations(&mut worker, &[1.0, 2.0]), vec![0.0, 1.0]);
+        assert_eq!(constraints.violation(&mut worker, &[2.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn handle_penalty_and_feasibility_rules() {
+        let penalty: ConstraintSet = set(&["a <= b"], ConstraintHandling::Penalty, &bounds());
+        assert_eq!(penalty.handle(2.0, 0.0), 2.0);
+        assert_eq!(penalty.handle(2.0, 0.5), 502.0);
+
+        let rules: ConstraintSet = set(&["a <= b"], ConstraintHandling::FeasibilityRules, &bounds());
+        assert_eq!(rules.handle(2.0, 0.0), 2.0);
+        assert_eq!(rules.handle(2.0, 0.5), 2.0);
+    }
+
+    #[test]
+    fn feasibility_rules_put_infeasible_individuals_after_the_feasible_ones() {
+        let objective = (|| (), |_: &mut (), values: &[f64]| -1000.0*values[0]);
+        let constrained = Constrained::new(objective, set(&["a <= 5"], ConstraintHandling::FeasibilityRules, &bounds()));
+        let mut worker = constrained.worker();
+
+        let mut population: Vec<Chromosome> = [7.0, 4.0, 6.0, 3.0]
+            .iter()
+            .map(|a| {
+                let mut c: Chromosome = Chromosome::new(vec![*a, 0.0]);
+                c.violation = constrained.violation(&mut worker, c.values());
+                c.set_fitness(constrained.evaluate(&mut worker, c.values()));
+                c
+            })
+            .collect();
+        assert_eq!(population[0].fitness, -7000.0);
+        assert_eq!(population[0].violation, 2.0);
+
+        sort_population(&mut population, true);
+        let order: Vec<f64> = population.iter().map(|c| c.values()[0]).collect();
+        assert_eq!(order, vec![4.0, 3.0, 6.0, 7.0]);
+
+        sort_population(&mut population, false);
+        let order: Vec<f64> = population.iter().map(|c| c.values()[0]).collect();
+        assert_eq!(order, vec![3.0, 4.0, 6.0, 7.0]);
+    }
+
+    #[test]
+    fn repair_reaches_the_feasible_region() {
+        let constraints: ConstraintSet = set(&["a + b <= 4"], ConstraintHandling::Repair, &bounds());
+        let mut worker: ConstraintWorker = constraints.worker();
+        let repaired: Vec<f64> = constraints.repair(&mut worker, &[5.0, 3.0]);
+        assert!(constraints.violation(&mut worker, &repaired) <= 1e-9);
+        assert_eq!(constraints.repair(&mut worker, &[1.0, 2.0]), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn repair_rounds_integer_parameters() {
+        let mut bounds: Vec<Bound> = bounds();
+        bounds[0].kind = Kind::Integer;
+        let constraints: ConstraintSet = set(&["a <= 2.5"], ConstraintHandling::Repair, &bounds);
+        let mut worker: ConstraintWorker = constraints.worker();
+        let repaired: Vec<f64> = constraints.repair(&mut worker, &[7.0, 1.0]);
+        assert_eq!(repaired[0], repaired[0].round());
+        assert!(repaired[0] <= 2.0);
+    }
+}
@@ -0,0 +1,153 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use vecshard::ShardExt;
+
+use super::ga_json::Bound;
+
+//operator used to combine the genes of two parents
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Crossover {
+    //single cut at crossover_rate * number of genes
+    #[default]
+    SinglePoint,
+    //simulated binary crossover, larger `eta` keeps the children closer to the parents
+    SimulatedBinary { eta: f64 },
+    //each gene is drawn from the parents interval extended by `alpha` times its length
+    BlendAlpha { alpha: f64 },
+    //whole arithmetic crossover with a random weight for each pair of parents
+    Arithmetic,
+    //each gene is swapped between the children with the given probability
+    Uniform { probability: f64 },
+}
+
+impl Crossover {
+
+    //the probability of the uniform crossover must be in [0, 1]
+    pub fn validate(&self) -> Result<(),String> {
+        match self {
+            Crossover::Uniform { probability } if !(0.0..=1.0).contains(probability) =>
+                Err(format!("the probability of the uniform crossover must be between 0 and 1, found {}", probability)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn recombine<R: Rng + ?Sized>(&self, p1: &[f64], p2: &[f64], crossover_rate: f64, bounds: &[Bound], rng: &mut R) -> (Vec<f64>,Vec<f64>) {
+
+        let (mut c1, mut c2) = match self {
+            Crossover::SinglePoint => single_point(p1, p2, crossover_rate),
+            Crossover::SimulatedBinary { eta } => simulated_binary(p1, p2, *eta, bounds, rng),
+            Crossover::BlendAlpha { alpha } => blend_alpha(p1, p2, *alpha, rng),
+            Crossover::Arithmetic => arithmetic(p1, p2, rng),
+            Crossover::Uniform { probability } => uniform(p1, p2, *probability, rng),
+        };
+
+        for (i, bound) in bounds.iter().enumerate().take(c1.len()) {
+            c1[i] = bound.clamp(c1[i]);
+            c2[i] = bound.clamp(c2[i]);
+        }
+        (c1, c2)
+    }
+}
+
+fn single_point(p1: &[f64], p2: &[f64], crossover_rate: f64) -> (Vec<f64>,Vec<f64>) {
+
+    let cut: usize = ((crossover_rate * p1.len() as f64) as usize).min(p1.len());
+
+    let (left_child_1, right_child_2) = p1.to_vec().split_inplace_at(cut);
+    let (right_child_1, left_child_2) = p2.to_vec().split_inplace_at(cut);
+
+    let mut left_vec: Vec<f64> = left_child_1.into();
+    left_vec.append(&mut left_child_2.into());
+
+    let mut right_vec: Vec<f64> = right_child_1.into();
+    right_vec.append(&mut right_child_2.into());
+
+    (left_vec, right_vec)
+}
+
+//bounded SBX (Deb & Agrawal), each gene is crossed with probability 0.5
+fn simulated_binary<R: Rng + ?Sized>(p1: &[f64], p2: &[f64], eta: f64, bounds: &[Bound], rng: &mut R) -> (Vec<f64>,Vec<f64>) {
+
+    let mut c1: Vec<f64> = p1.to_vec();
+    let mut c2: Vec<f64> = p2.to_vec();
+
+    for i in 0..p1.len() {
+
+        if rng.gen_bool(0.5) || (p1[i] - p2[i]).abs() < 1.0e-14 {
+            continue;
+        }
+
+        let y1: f64 = p1[i].min(p2[i]);
+        let y2: f64 = p1[i].max(p2[i]);
+        let (lower, upper) = (bounds[i].min, bounds[i].max);
+        let u: f64 = rng.gen_range(0.0..1.0);
+
+        let beta_q = |beta: f64| -> f64 {
+            let alpha: f64 = 2.0 - beta.powf(-(eta + 1.0));
+            if u <= 1.0/alpha {
+                (u*alpha).powf(1.0/(eta + 1.0))
+            }
+            else {
+                (1.0/(2.0 - u*alpha)).powf(1.0/(eta + 1.0))
+            }
+        };
+
+        let beta_1: f64 = 1.0 + 2.0*(y1 - lower)/(y2 - y1);
+        let beta_2: f64 = 1.0 + 2.0*(upper - y2)/(y2 - y1);
+
+        let mut child_1: f64 = 0.5*((y1 + y2) - beta_q(beta_1)*(y2 - y1));
+        let mut child_2: f64 = 0.5*((y1 + y2) + beta_q(beta_2)*(y2 - y1));
+
+        if rng.gen_bool(0.5) {
+            std::mem::swap(&mut child_1, &mut child_2);
+        }
+        c1[i] = child_1;
+        c2[i] = child_2;
+    }
+    (c1, c2)
+}
+
+fn blend_alpha<R: Rng + ?Sized>(p1: &[f64], p2: &[f64], alpha: f64, rng: &mut R) -> (Vec<f64>,Vec<f64>) {
+
+    let mut c1: Vec<f64> = Vec::with_capacity(p1.len());
+    let mut c2: Vec<f64> = Vec::with_capacity(p1.len());
+
+    for (a, b) in p1.iter().zip(p2) {
+        let d: f64 = (a - b).abs();
+        let lower: f64 = a.min(*b) - alpha*d;
+        let upper: f64 = a.max(*b) + alpha*d;
+        if upper > lower {
+            c1.push(rng.gen_range(lower..=upper));
+            c2.push(rng.gen_range(lower..=upper));
+        }
+        else {
+            c1.push(*a);
+            c2.push(*b);
+        }
+    }
+    (c1, c2)
+}
+
+fn arithmetic<R: Rng + ?Sized>(p1: &[f64], p2: &[f64], rng: &mut R) -> (Vec<f64>,Vec<f64>) {
+
+    let w: f64 = rng.gen_range(0.0..=1.0);
+
+    let c1: Vec<f64> = p1.iter().zip(p2).map(|(a, b)| w*a + (1.0 - w)*b).collect();
+    let c2: Vec<f64> = p1.iter().zip(p2).map(|(a, b)| (1.0 - w)*a + w*b).collect();
+
+    (c1, c2)
+}
+
+fn uniform<R: Rng + ?Sized>(p1: &[f64], p2: &[f64], probability: f64, rng: &mut R) -> (Vec<f64>,Vec<f64>) {
+
+    let mut c1: Vec<f64> = p1.to_vec();
+    let mut c2: Vec<f64> = p2.to_vec();
+
+    for i in 0..p1.len() {
+        if rng.gen_bool(probability.clamp(0.0, 1.0)) {
+            std::mem::swap(&mut c1[i], &mut c2[i]);
+        }
+    }
+    (c1, c2)
+}
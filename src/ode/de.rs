@@ -0,0 +1,231 @@
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{is_fitter, Chromosome};
+use super::ga_json::{Bound, GA_Metadata};
+use super::hall_of_fame::HallOfFameConfig;
+use super::history::History;
+use super::initialization::Initialization;
+use super::objective::{evaluate_population, thread_pool, Objective};
+use super::observer::Observer;
+use super::optimizer::{run_seed, OptimizationResult, Optimizer, RunTracker};
+use super::termination::{StopReason, Termination};
+
+//how the base vector and the differences of the mutant vector are chosen
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub enum DeStrategy {
+    #[default]
+    #[serde(rename = "rand/1/bin")]
+    Rand1,
+    #[serde(rename = "best/1/bin")]
+    Best1,
+    #[serde(rename = "current-to-best/1/bin")]
+    CurrentToBest1,
+}
+
+//what is done with a trial gene outside its bound
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundHandling {
+    //mirrored back into the interval at the violated bound
+    #[default]
+    Reflection,
+    //drawn again uniformly inside the bound
+    Reinitialization,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeParameters {
+    #[serde(default)]
+    pub strategy: DeStrategy,
+    pub f: f64, //differential weight
+    pub cr: f64, //crossover probability
+    #[serde(default)]
+    pub bound_handling: BoundHandling,
+}
+
+#[derive(Debug, Clone)]
+pub struct DifferentialEvolution {
+    parameters: DeParameters,
+    bounds: Vec<Bound>,
+    minimization: bool,
+    population_size: usize,
+    max_iterations: usize,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    initialization: Initialization,
+    threads: Option<usize>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    pub population: Vec<Chromosome>,
+    history: History,
+}
+
+impl DifferentialEvolution {
+
+    pub fn from_metadata(metadata: &GA_Metadata, parameters: DeParameters, bounds: Vec<Bound>, is_min: bool) -> Self {
+        let seed: u64 = run_seed(metadata);
+        Self {
+            parameters,
+            bounds,
+            minimization: is_min,
+            population_size: metadata.population_size,
+            max_iterations: metadata.max_iterations,
+            termination: metadata.termination.clone(),
+            hall_of_fame: metadata.hall_of_fame.clone(),
+            initialization: metadata.initialization.clone(),
+            threads: metadata.threads,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            population: vec![],
+            history: History::default(),
+        }
+    }
+
+    //indexes of `count` distinct individuals different from `exclude` (repeated when the population is too small)
+    fn pick(&mut self, exclude: usize, count: usize) -> Vec<usize> {
+
+        let n: usize = self.population.len();
+        let mut picked: Vec<usize> = vec![];
+
+        while picked.len() < count {
+            let r: usize = self.rng.gen_range(0..n);
+            if n > count && (r == exclude || picked.contains(&r)) {
+                continue;
+            }
+            picked.push(r);
+        }
+        picked
+    }
+
+    fn trial(&mut self, target: usize, best: usize) -> Chromosome {
+
+        let f: f64 = self.parameters.f;
+        let r: Vec<usize> = self.pick(target, 3);
+        let x = |i: usize, j: usize| self.population[i].values()[j];
+        let dimension: usize = self.bounds.len();
+
+        let mutant: Vec<f64> = (0..dimension)
+            .map(|j| match self.parameters.strategy {
+                DeStrategy::Rand1 => x(r[0], j) + f*(x(r[1], j) - x(r[2], j)),
+                DeStrategy::Best1 => x(best, j) + f*(x(r[0], j) - x(r[1], j)),
+                DeStrategy::CurrentToBest1 => x(target, j) + f*(x(best, j) - x(target, j)) + f*(x(r[0], j) - x(r[1], j)),
+            })
+            .collect();
+
+        //binomial crossover, at least one gene comes from the mutant
+        let j_rand: usize = self.rng.gen_range(0..dimension);
+        let mut values: Vec<f64> = self.population[target].get_values();
+
+        for j in 0..dimension {
+            if j == j_rand || self.rng.gen_range(0.0..1.0) < self.parameters.cr {
+                values[j] = self.handle_bound(mutant[j], j);
+            }
+        }
+        Chromosome::new(values)
+    }
+
+    fn handle_bound(&mut self, value: f64, j: usize) -> f64 {
+
+        let bound: &Bound = &self.bounds[j];
+        if value >= bound.min && value <= bound.max {
+            return value;
+        }
+
+        match self.parameters.bound_handling {
+            BoundHandling::Reflection => {
+                let reflected: f64 = if value < bound.min { 2.0*bound.min - value } else { 2.0*bound.max - value };
+                bound.clamp(reflected)
+            },
+            BoundHandling::Reinitialization => self.rng.gen_range(bound.min..=bound.max),
+        }
+    }
+}
+
+impl<O: Objective> Optimizer<O> for DifferentialEvolution {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> {
+
+        let start: Instant = Instant::now();
+
+        if self.bounds.is_empty() {
+            println!("Differential evolution needs at least one bound");
+            return Err(());
+        }
+
+        let pool: ThreadPool = thread_pool(self.threads)?;
+
+        if self.population.is_empty() {
+            self.population = self.initialization.population(&self.bounds, self.population_size, &mut self.rng)?;
+        }
+        let evaluations: usize = evaluate_population(&pool, &mut self.population, objective);
+
+        let mut tracker: RunTracker = RunTracker::new(start, &self.population, &self.bounds, evaluations, self.minimization, self.hall_of_fame.as_ref(), observer)?;
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(reason) = tracker.next_iteration(&self.termination, self.max_iterations, observer) {
+                break reason;
+            }
+
+            let best: usize = (0..self.population.len())
+                .reduce(|b, i| if is_fitter(&self.population[i], &self.population[b], self.minimization) { i } else { b })
+                .unwrap_or(0);
+
+            let mut trials: Vec<Chromosome> = (0..self.population.len()).map(|i| self.trial(i, best)).collect();
+            tracker.evaluations += evaluate_population(&pool, &mut trials, objective);
+
+            //a trial replaces its target when it is at least as good
+            for (target, trial) in self.population.iter_mut().zip(trials) {
+                if !is_fitter(target, &trial, self.minimization) {
+                    *target = trial;
+                }
+            }
+
+            tracker.end_iteration(&self.population, &self.bounds, observer);
+        };
+
+        let result: OptimizationResult = tracker.finish(stop_reason, observer);
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::observer::ConsoleObserver;
+
+    type Quadratic = (fn(), fn(&mut (), &[f64]) -> f64);
+
+    #[test]
+    fn converges_to_the_minimum_of_a_quadratic() {
+        let bounds: Vec<Bound> = vec![Bound::new(String::from("x"), -5.0, 5.0), Bound::new(String::from("y"), -5.0, 5.0)];
+        let objective: Quadratic = (|| (), |_, values| (values[0] - 1.0).powi(2) + (values[1] + 2.0).powi(2));
+        let metadata: GA_Metadata = GA_Metadata { population_size: 20, max_iterations: 150, seed: Some(1), threads: Some(1), ..Default::default() };
+        let parameters: DeParameters = DeParameters { strategy: DeStrategy::Rand1, f: 0.5, cr: 0.9, bound_handling: BoundHandling::Reflection };
+
+        let result: OptimizationResult = DifferentialEvolution::from_metadata(&metadata, parameters, bounds, true)
+            .optimize(&objective, &mut ConsoleObserver)
+            .unwrap();
+        assert!(result.best.fitness < 1e-8);
+        assert!((result.best.values()[0] - 1.0).abs() < 1e-4 && (result.best.values()[1] + 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,92 @@
+use super::ga::Chromosome;
+use super::ga_json::Bound;
+
+//mean euclidean distance between every pair of individuals, with each gene normalized by the width of its bound
+//the result is between 0 (identical individuals) and 1
+pub fn mean_pairwise_distance(population: &[Chromosome], bounds: &[Bound]) -> f64 {
+
+    let n: usize = population.len();
+    if n < 2 || bounds.is_empty() {
+        return 0.0;
+    }
+
+    let normalized: Vec<Vec<f64>> = population
+                        .iter()
+                        .map(|c| normalize(&c.get_values(), bounds))
+                        .collect();
+
+    let mut total: f64 = 0.0;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            total += distance(&normalized[i], &normalized[j]);
+        }
+    }
+
+    let pairs: f64 = (n*(n - 1)/2) as f64;
+    total / pairs / (bounds.len() as f64).sqrt()
+}
+
+//maps each value to [0, 1] according to its bound
+pub fn normalize(values: &[f64], bounds: &[Bound]) -> Vec<f64> {
+    values
+        .iter()
+        .zip(bounds)
+        .map(|(v, b)| if b.max > b.min { (v - b.min)/(b.max - b.min) } else { 0.0 })
+        .collect()
+}
+
+pub fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y)*(x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+//variance of each gene normalized by the width of its bound
+pub fn gene_variance(population: &[Chromosome], bounds: &[Bound]) -> Vec<f64> {
+
+    let n: usize = population.len();
+    if n == 0 {
+        return vec![0.0; bounds.len()];
+    }
+
+    let normalized: Vec<Vec<f64>> = population.iter().map(|c| normalize(c.values(), bounds)).collect();
+
+    (0..bounds.len())
+        .map(|j| {
+            let mean: f64 = normalized.iter().map(|v| v[j]).sum::<f64>()/n as f64;
+            normalized.iter().map(|v| (v[j] - mean)*(v[j] - mean)).sum::<f64>()/n as f64
+        })
+        .collect()
+}
+
+const ENTROPY_BINS: usize = 10;
+
+//Shannon entropy of the histogram of each normalized gene (bins of equal width) divided by its maximum,
+//averaged over the genes, between 0 (identical individuals) and 1 (genes spread evenly over the bounds)
+pub fn entropy(population: &[Chromosome], bounds: &[Bound]) -> f64 {
+
+    let n: usize = population.len();
+    if n < 2 || bounds.is_empty() {
+        return 0.0;
+    }
+
+    let bins: usize = ENTROPY_BINS.min(n);
+    let mut total: f64 = 0.0;
+
+    for (j, bound) in bounds.iter().enumerate() {
+        let mut counts: Vec<usize> = vec![0; bins];
+        for c in population.iter() {
+            let u: f64 = normalize(&c.values()[j..j + 1], std::slice::from_ref(bound))[0];
+            counts[((u*bins as f64) as usize).min(bins - 1)] += 1;
+        }
+        total -= counts
+            .iter()
+            .filter(|k| **k > 0)
+            .map(|k| { let p: f64 = *k as f64/n as f64; p*p.ln() })
+            .sum::<f64>();
+    }
+
+    total/bounds.len() as f64/(bins as f64).ln()
+}
@@ -0,0 +1,113 @@
+use nalgebra::DMatrix;
+
+use super::ga::Chromosome;
+use super::ga_json::Bound;
+use super::history::GenerationStats;
+use super::niching::Niche;
+use super::nsga2::ParetoSolution;
+use super::objective::Objective;
+use super::observer::{Control, Observer};
+use super::optimizer::OptimizationResult;
+
+//the optimizers search the space of the encoded bounds (see Bound::encoded), the values are decoded
+//to the scale and kind of each parameter before the evaluation and the reporting
+
+pub fn encode(bounds: &[Bound], values: &[f64]) -> Vec<f64> {
+    bounds.iter().zip(values.iter()).map(|(b, v)| b.encode(*v)).collect()
+}
+
+pub fn decode(bounds: &[Bound], values: &[f64]) -> Vec<f64> {
+    bounds.iter().zip(values.iter()).map(|(b, v)| b.decode(*v)).collect()
+}
+
+pub fn decode_chromosome(bounds: &[Bound], c: &Chromosome) -> Chromosome {
+    c.with_values(decode(bounds, c.values()))
+}
+
+//covariance of the natural values from the covariance at `values` of the search space (delta method)
+pub fn decode_covariance(bounds: &[Bound], values: &[f64], covariance: &DMatrix<f64>) -> DMatrix<f64> {
+    let jacobian: DMatrix<f64> = DMatrix::from_diagonal(&nalgebra::DVector::from_iterator(
+        bounds.len(), bounds.iter().zip(values.iter()).map(|(b, v)| b.derivative(*v))));
+    &jacobian*covariance*&jacobian
+}
+
+pub fn decode_result(bounds: &[Bound], result: &OptimizationResult) -> OptimizationResult {
+    OptimizationResult {
+        best: decode_chromosome(bounds, &result.best),
+        covariance: result.covariance.as_ref().map(|c| decode_covariance(bounds, result.best.values(), c)),
+        pareto_front: result.pareto_front
+            .iter()
+            .map(|s| ParetoSolution { chromosome: decode_chromosome(bounds, &s.chromosome), ..s.clone() })
+            .collect(),
+        hall_of_fame: result.hall_of_fame.iter().map(|c| decode_chromosome(bounds, c)).collect(),
+        niches: result.niches.iter().map(|n| Niche { best: decode_chromosome(bounds, &n.best), ..n.clone() }).collect(),
+        ..result.clone()
+    }
+}
+
+//objective evaluated at the natural values of the parameters
+pub struct Encoded<O> {
+    objective: O,
+    bounds: Vec<Bound>,
+}
+
+impl<O: Objective> Encoded<O> {
+    pub fn new(objective: O, bounds: Vec<Bound>) -> Self {
+        Self { objective, bounds }
+    }
+}
+
+impl<O: Objective> Objective for Encoded<O> {
+    type Worker = O::Worker;
+
+    fn worker(&self) -> O::Worker {
+        self.objective.worker()
+    }
+
+    fn evaluate(&self, worker: &mut O::Worker, values: &[f64]) -> f64 {
+        self.objective.evaluate(worker, &decode(&self.bounds, values))
+    }
+
+    fn residuals(&self, worker: &mut O::Worker, values: &[f64]) -> Option<Vec<f64>> {
+        self.objective.residuals(worker, &decode(&self.bounds, values))
+    }
+
+    fn objectives(&self, worker: &mut O::Worker, values: &[f64]) -> (f64, Vec<f64>) {
+        self.objective.objectives(worker, &decode(&self.bounds, values))
+    }
+
+    fn violation(&self, worker: &mut O::Worker, values: &[f64]) -> f64 {
+        self.objective.violation(worker, &decode(&self.bounds, values))
+    }
+}
+
+//passes the individuals to another observer with the natural values of the parameters
+pub struct DecodedObserver<'a> {
+    observer: &'a mut dyn Observer,
+    bounds: &'a [Bound],
+}
+
+impl<'a> DecodedObserver<'a> {
+    pub fn new(observer: &'a mut dyn Observer, bounds: &'a [Bound]) -> Self {
+        Self { observer, bounds }
+    }
+}
+
+impl Observer for DecodedObserver<'_> {
+
+    fn on_generation_start(&mut self, generation: usize) -> Control {
+        self.observer.on_generation_start(generation)
+    }
+
+    fn on_generation_end(&mut self, stats: &GenerationStats, best: &Chromosome) -> Control {
+        self.observer.on_generation_end(stats, &decode_chromosome(self.bounds, best))
+    }
+
+    fn on_new_best(&mut self, generation: usize, best: &Chromosome) -> Control {
+        self.observer.on_new_best(generation, &decode_chromosome(self.bounds, best))
+    }
+
+    fn on_termination(&mut self, result: &OptimizationResult) {
+        self.observer.on_termination(&decode_result(self.bounds, result))
+    }
+}
@@ -1,39 +1,53 @@
 use std::cmp::Ordering;
-use std::fs::File;
-use std::path::Path;
-use std::io::Write;
 use std::vec;
 use quicksort::quicksort_by;
-use rand::Rng;
-//use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
-use vecshard::ShardExt;
+use rand::{Rng, RngCore, SeedableRng};
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use rayon::ThreadPool;
 use std::fmt;
-use anyhow::Error;
-
-use super::ga_json::Bound;
-
-#[derive(Debug,Clone,Default)]
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+
+use super::ga_json::{Bound, GA_Metadata};
+use super::checkpoint::{save_checkpoint, Checkpoint, CheckpointConfig};
+use super::crossover::Crossover;
+use super::hall_of_fame::HallOfFameConfig;
+use super::history::{History, Refinement, Restart};
+use super::initialization::Initialization;
+use super::local_search::LocalSearch;
+use super::mutation::Mutation;
+use super::niching::{Niching, NichingMethod};
+use super::objective::{evaluate_population, thread_pool, Objective};
+use super::observer::Observer;
+use super::optimizer::{OptimizationResult, Optimizer, RunTracker};
+use super::replacement::Replacement;
+use super::restart::RestartPolicy;
+use super::selection::Selection;
+use super::termination::{StopReason, Termination};
+
+#[derive(Debug,Clone,Default,Serialize,Deserialize)]
 pub struct Chromosome {
     values: Vec<f64>, //genes 
     pub fitness: f64,
     mutation_percentage: f64,
+    steps: Vec<f64>, //step size of each gene, used by the self-adaptive mutation
+    #[serde(default)]
+    evaluated: bool, //the fitness belongs to the current values, the individual is not evaluated again
+    #[serde(default)]
+    pub violation: f64, //total violation of the constraints compared by the feasibility rules, 0 if feasible
 }
 
 impl Chromosome {
 
-    pub fn new_empty() -> Self {
-        Self {
-            values: vec![],
-            fitness: 0.0,
-            mutation_percentage: 0.1,            
-        }
-    }
-
     pub fn new(v: Vec<f64>) -> Self {
         Self {
             values: v,
             fitness: 0.0,
             mutation_percentage: 0.1,            
+            steps: vec![],
+            evaluated: false,
+            violation: 0.0,
         }
     } 
 
@@ -41,34 +55,120 @@ impl Chromosome {
         return self.values.clone();
     }   
 
-    fn mutation(&mut self, mutation_rate: f64, bounds: &Vec<Bound>) {
-      
-        let mut rng =  rand::thread_rng();  
-        let c_index: usize = rng.gen_range(0..self.values.len());
-        let mut p: f64 = rng.gen_range(0.0..=1.0);
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
 
-        if p < mutation_rate {
-      
-            p = rng.gen_range(0.0..=1.0);
+    //copy of the individual with other values
+    pub fn with_values(&self, values: Vec<f64>) -> Self {
+        Self {
+            values,
+            evaluated: false,
+            ..self.clone()
+        }
+    }
 
-            let mut temp_value = self.mutation_percentage*self.values[c_index];
-            if temp_value > 1. {
-                temp_value = 1.;
-            }
+    pub fn mutation<R: Rng + ?Sized>(&mut self, operator: &Mutation, mutation_rate: f64, bounds: &[Bound], rng: &mut R) {
       
-            if p < 0.5 {
-                self.values[c_index] += temp_value;
-                if self.values[c_index] > bounds[c_index].max {
-                    self.values[c_index] = bounds[c_index].max;
-                }
-            }
-            else {
-                self.values[c_index] -= temp_value;
-                if self.values[c_index] < bounds[c_index].min {
-                    self.values[c_index] = bounds[c_index].min;
-                }
-            }
+        let values: Vec<f64> = self.values.clone();
+        operator.mutate(&mut self.values, &mut self.steps, self.mutation_percentage, mutation_rate, bounds, rng);
+        if self.values != values {
+            self.evaluated = false;
+        }
+    }
+
+    //new fitness of the individual, it is not evaluated again until its values change
+    pub fn set_fitness(&mut self, fitness: f64) {
+        self.fitness = fitness;
+        self.evaluated = true;
+    }
+
+    pub fn is_evaluated(&self) -> bool {
+        self.evaluated
+    }
+
+    //a child equal to one of its parents keeps the fitness of that parent
+    fn inherit(&mut self, parents: [&Chromosome; 2]) {
+        if let Some(parent) = parents.iter().find(|p| p.evaluated && p.values == self.values) {
+            self.fitness = parent.fitness;
+            self.violation = parent.violation;
+            self.evaluated = true;
+        }
+    }
+}
+
+//true if fitness `a` is better than fitness `b`
+pub fn is_better(a: f64, b: f64, minimization: bool) -> bool {
+    if minimization {
+        a < b
+    }
+    else {
+        a > b
+    }
+}
+
+//true if individual `a` is better than `b` by Deb's feasibility rules: the smaller violation of the constraints wins,
+//the fitness decides between individuals with the same violation
+pub fn is_fitter(a: &Chromosome, b: &Chromosome, minimization: bool) -> bool {
+    if a.violation != b.violation {
+        return a.violation < b.violation;
+    }
+    is_better(a.fitness, b.fitness, minimization)
+}
+
+//order from the best to the worst individual
+pub fn fitness_order(a: &Chromosome, b: &Chromosome, minimization: bool) -> Ordering {
+    if is_fitter(a, b, minimization) {
+        Ordering::Less
+    }
+    else if is_fitter(b, a, minimization) {
+        Ordering::Greater
+    }
+    else {
+        Ordering::Equal
+    }
+}
+
+//individuals with each gene drawn uniformly from its bound
+pub fn random_population<R: Rng + ?Sized>(bounds: &[Bound], p_size: usize, rng: &mut R) -> Vec<Chromosome> {
+
+    let mut population: Vec<Chromosome> = vec![];
+
+    for _i in 0..p_size {
+
+        let mut values: Vec<f64> = vec![];
+
+        for bound in bounds.iter() {
+            values.push(rng.gen_range(bound.min..=bound.max));
         }
+        population.push(Chromosome::new(values));
+    }
+    population
+}
+
+//children of two parents, they inherit the mean step sizes of the parents and the fitness of an equal parent
+pub fn recombine<R: Rng + ?Sized>(p1: &Chromosome, p2: &Chromosome, operator: &Crossover, crossover_rate: f64, bounds: &[Bound],
+            rng: &mut R) -> (Chromosome,Chromosome) {
+
+    let (left_vec, right_vec) = operator.recombine(&p1.values, &p2.values, crossover_rate, bounds, rng);
+
+    let mut children: (Chromosome,Chromosome) = (Chromosome::new(left_vec),Chromosome::new(right_vec));
+    children.0.inherit([p1, p2]);
+    children.1.inherit([p1, p2]);
+
+    if !p1.steps.is_empty() && p1.steps.len() == p2.steps.len() {
+        let steps: Vec<f64> = p1.steps.iter().zip(&p2.steps).map(|(a, b)| 0.5*(a + b)).collect();
+        children.0.steps = steps.clone();
+        children.1.steps = steps;
+    }
+    children
+}
+
+//sorts the population from the best to the worst individual
+pub fn sort_population(population: &mut [Chromosome], minimization: bool) {
+    quicksort_by(population, |c1, c2| GA::compare(c1, c2, minimization));
+    if !minimization {
+        population.reverse();
     }
 }
 
@@ -85,7 +185,7 @@ impl fmt::Display for Chromosome {
     }    
 }
 
-#[derive(Debug,Clone,Default)]
+#[derive(Debug,Clone)]
 pub struct GA {
     max_generations: usize,
     mutation_rate: f64,
@@ -93,8 +193,33 @@ pub struct GA {
     pub population: Vec<Chromosome>,
     bounds: Vec<Bound>, //bound for each chromosome
     minimization: bool,
+    selection: Selection,
+    crossover_operator: Crossover,
+    mutation_operator: Mutation,
+    replacement: Replacement,
+    elitism: usize,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    threads: Option<usize>, //None uses every available core
+    seed: u64,
+    rng: ChaCha8Rng, //every random draw of the algorithm comes from this generator
+    checkpoint: Option<CheckpointConfig>,
+    config_hash: u64,
+    resume_state: Option<Checkpoint>,
+    population_size: usize, //size of the initial population created when the population is empty
+    initialization: Initialization,
+    local_search: Option<LocalSearch>,
+    restart: Option<RestartPolicy>,
+    niching: Option<Niching>, //keeps several optima in the population
+    history: History,
 } 
 
+impl Default for GA {
+    fn default() -> Self {
+        GA::new(0, 0.0, 0.0, vec![], true)
+    }
+}
+
 impl GA {
     pub fn new(max: usize, mut_rate: f64, cross_rate: f64, bounds: Vec<Bound>, is_min: bool) -> Self {
         Self {
@@ -103,97 +228,157 @@ impl GA {
             crossover_rate: cross_rate,
             population: vec![],
             bounds: bounds,
-            minimization: is_min
+            minimization: is_min,
+            selection: Selection::default(),
+            crossover_operator: Crossover::default(),
+            mutation_operator: Mutation::default(),
+            replacement: Replacement::default(),
+            elitism: 0,
+            termination: Termination::default(),
+            hall_of_fame: None,
+            threads: None,
+            seed: 0,
+            rng: ChaCha8Rng::seed_from_u64(0),
+            checkpoint: None,
+            config_hash: 0,
+            resume_state: None,
+            population_size: 0,
+            initialization: Initialization::default(),
+            local_search: None,
+            restart: None,
+            niching: None,
+            history: History::default(),
         }
+        .with_seed(rand::thread_rng().next_u64())
     }
 
-    pub fn generate_random_population(&mut self, p_size: usize, c_size: usize){
-      
-        let mut rng = rand::thread_rng();
-      
-        for _i in 0..p_size {
+    pub fn from_metadata(metadata: &GA_Metadata, bounds: Vec<Bound>, is_min: bool) -> Self {
+        let mut ga: GA = GA::new(metadata.max_iterations, metadata.mutation_rate, metadata.crossover_rate, bounds, is_min);
+        ga.selection = metadata.selection.clone();
+        ga.crossover_operator = metadata.crossover.clone();
+        ga.mutation_operator = metadata.mutation.clone();
+        ga.replacement = metadata.replacement.clone();
+        ga.elitism = metadata.elitism;
+        ga.termination = metadata.termination.clone();
+        ga.hall_of_fame = metadata.hall_of_fame.clone();
+        ga.threads = metadata.threads;
+        ga.checkpoint = metadata.checkpoint.clone();
+        ga.population_size = metadata.population_size;
+        ga.initialization = metadata.initialization.clone();
+        ga.local_search = metadata.local_search.clone();
+        ga.restart = metadata.restart.clone();
+        ga.niching = metadata.niching.clone();
+        if let Some(seed) = metadata.seed {
+            ga = ga.with_seed(seed);
+        }
+        ga
+    }
 
-            let mut values: Vec<f64> = vec![];
+    //restarts the random generator, two runs with the same seed and configuration give the same result
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self
+    }
 
-            for j in 0..c_size {
-                values.push(rng.gen_range(self.bounds[j].min..=self.bounds[j].max));
-            }
-            self.population.push(Chromosome::new(values));
+    //hash of the configuration saved in the checkpoints, to detect a resume with a different configuration
+    pub fn with_config_hash(mut self, config_hash: u64) -> Self {
+        self.config_hash = config_hash;
+        self
+    }
+
+    //creates the initial population if it is empty, evaluates and sorts it, returns the number of evaluations
+    pub fn initialize<O: Objective>(&mut self, pool: &ThreadPool, objective: &O) -> Result<usize,()> {
+
+        if self.population.is_empty() {
+            self.population = self.initialization.population(&self.bounds, self.population_size, &mut self.rng)?;
         }
+        let evaluations: usize = evaluate_population(pool, &mut self.population, objective);
+        
+        sort_population(&mut self.population, self.minimization);
 
+        Ok(evaluations)
     }
 
-    fn select_parents(&self) -> (&Chromosome,&Chromosome){ 
-      
-        let upper_bound: f64 = self.population
-                                    .iter()
-                                    .map(|c| c.fitness)
-                                    .sum();
-      
-        let mut rng = rand::thread_rng();
+    //selection, crossover, mutation, evaluation and replacement, returns the number of evaluations
+    //with deterministic crowding every child competes with its closest parent instead of the replacement
+    pub fn next_generation<O: Objective>(&mut self, pool: &ThreadPool, objective: &O) -> usize {
+
         let p_size: usize = self.population.len();
+        let crowding: bool = self.niching.as_ref().is_some_and(|n| n.method == NichingMethod::Crowding);
+        let offspring_size: usize = if crowding { p_size - p_size % 2 } else { self.replacement.offspring_count(p_size, self.elitism) };
+        let pairs: Vec<(usize,usize)> = if crowding { self.random_pairs() } else { self.select_parents(offspring_size.div_ceil(2)) };
+        let mut offspring: Vec<Chromosome> = vec![];
 
-        let index1: usize = rng.gen_range(0..p_size);
-        let mut index2: usize = rng.gen_range(0..p_size);
-      
-        while index2 == index1 {
-            index2 = rng.gen_range(0..p_size);
+        for (p1, p2) in pairs.iter() { 
+
+            let new_individuals: (Chromosome,Chromosome) = self.crossover(*p1, *p2);
+            
+            offspring.push(new_individuals.0);
+            offspring.push(new_individuals.1);
         }
+        offspring.truncate(offspring_size);
 
-        let mut parents: (&Chromosome,&Chromosome) = (&self.population[index1],&self.population[index2]);
-      
-        let mut prob_1: f64 = rng.gen_range(0.0..=upper_bound);
-        let mut prob_2: f64 = rng.gen_range(0.0..=upper_bound);
-        let mut i: usize = 0;
-
-        // Rotate the roulette
-        while (prob_1 > 0.0 || prob_2 > 0.0) && i < p_size {
-            if prob_1 > 0.0 {
-                if self.minimization {
-                    prob_1 -= upper_bound - self.population[i].fitness;
-                }
-                else {
-                    prob_1 -= self.population[i].fitness;    
-                }
-                if prob_1 <= 0.0 {
-                    parents.0 = &self.population[i];
-                }
-            }
-            else if prob_2 > 0.0 {
-                if self.minimization {
-                    prob_2 -= upper_bound - self.population[i].fitness;                    
-                }
-                else {
-                    prob_2 -= self.population[i].fitness;
-                }
-                if prob_2 <= 0.0 {
-                    parents.1 = &self.population[i];
-                }
-            }
-            i += 1;
+        //mutate and calculate fitness of each child
+        for child in offspring.iter_mut() {
+            
+            child.mutation(&self.mutation_operator, self.mutation_rate, &self.bounds, &mut self.rng);
+        }               
+        let evaluations: usize = evaluate_population(pool, &mut offspring, objective);
+
+        match &self.niching {
+            Some(niching) if crowding => {
+                let families: Vec<(usize,usize,Chromosome,Chromosome)> = pairs
+                    .into_iter()
+                    .zip(offspring.chunks_exact(2))
+                    .map(|((p1, p2), children)| (p1, p2, children[0].clone(), children[1].clone()))
+                    .collect();
+                niching.crowding(&mut self.population, families, &self.bounds, self.minimization);
+            },
+            //sharing and clearing only change the selection, the survivors are chosen by their own fitness
+            _ => {
+                let parents: Vec<Chromosome> = std::mem::take(&mut self.population);
+                self.population = self.replacement.replace(parents, offspring, self.elitism, self.minimization);
+            },
         }
-        parents
+
+        evaluations
     }
-    
-    fn crossover(&self, parents: (&Chromosome,&Chromosome)) -> (Chromosome,Chromosome){
 
-        let length: f64 = parents.0.values.len() as f64;
-        let number_of_chromosomes: i32 = (self.crossover_rate * length) as i32;
-        
-        let (left_child_1, right_child_2) = parents.0.values.clone().split_inplace_at(number_of_chromosomes as usize);
-        let (right_child_1, left_child_2 ) = parents.1.values.clone().split_inplace_at(number_of_chromosomes as usize);
+    //disjoint pairs of parents of the deterministic crowding, each individual is in at most one pair
+    fn random_pairs(&mut self) -> Vec<(usize,usize)> {
 
-        let mut left_vec: Vec<f64> = left_child_1.into();
-        left_vec.append(&mut left_child_2.into());
+        let mut order: Vec<usize> = (0..self.population.len()).collect();
+        order.shuffle(&mut self.rng);
+        order.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+    }
 
-        let mut right_vec: Vec<f64> = right_child_1.into();
-        right_vec.append(&mut right_child_2.into());
-        
-        (Chromosome::new(left_vec),Chromosome::new(right_vec))
+    //pairs of distinct parents (indexes into the population) chosen by the selection strategy, with sharing and clearing
+    //the selection sees the modified fitness
+    fn select_parents(&mut self, pairs: usize) -> Vec<(usize,usize)> {
+
+        let adjusted: Option<Vec<Chromosome>> = self.niching
+            .as_ref()
+            .filter(|n| n.method != NichingMethod::Crowding)
+            .map(|n| n.adjusted(&self.population, &self.bounds, self.minimization));
+
+        self.selection.select_pairs(adjusted.as_deref().unwrap_or(&self.population), pairs, self.minimization, &mut self.rng)
     }
+    
+    fn crossover(&mut self, p1: usize, p2: usize) -> (Chromosome,Chromosome){
+
+        recombine(&self.population[p1], &self.population[p2], &self.crossover_operator, self.crossover_rate, &self.bounds, &mut self.rng)
+    }
+
+    //ascending fitness for the minimization, the population is reversed for the maximization,
+    //so the smaller violation comes first only for the minimization
+    fn compare(c1: &Chromosome, c2: &Chromosome, minimization: bool) -> Ordering {
+
+        if c1.violation != c2.violation {
+            let ordering: Ordering = c1.violation.partial_cmp(&c2.violation).unwrap_or(Ordering::Equal);
+            return if minimization { ordering } else { ordering.reverse() };
+        }
 
-    fn compare(c1: &Chromosome, c2: &Chromosome) -> Ordering {
-        
         if c1.fitness < c2.fitness {
             Ordering::Less
         } 
@@ -204,99 +389,142 @@ impl GA {
             Ordering::Equal
         }
     }
+}
 
-    pub fn optimize<F: FnMut(&Vec<f64>) -> f64> (&mut self, mut fitness_function: F) -> Result<Chromosome,()> 
-    //where F: FnMut(&Vec<f64>) + Send + Sync + 'static 
-    { 
-        
-        let mut best: Chromosome = Chromosome::new_empty();
-
-        /*self.population
-                .par_iter_mut()
-                .for_each(| c | 
-                {
-                    c.fitness = fitness_function(&c.values);
-                });*/
-        
-        self.population
-            .iter_mut()
-            .for_each(| c | {
-                c.fitness = fitness_function(&c.values);
-            });
-                
-        let mut i: usize = 0;
-        let mut solutions: Vec<String> = vec![];
+impl<O: Objective> Optimizer<O> for GA {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
 
-        while i < self.max_generations {
+    fn history(&self) -> &History {
+        &self.history
+    }
 
-            println!("iteration {:?}: ", i);            
-            let mut p_size: usize = self.population.len();
-            //println!("p_size is {:?}", p_size); 
+    fn seed(&self) -> u64 {
+        self.seed
+    }
 
-            for _j in 0..(p_size/4) { 
+    //the next call to optimize continues the run saved in the checkpoint
+    fn resume(&mut self, checkpoint: Checkpoint) -> bool {
+        self.population = checkpoint.population.clone();
+        self.seed = checkpoint.seed;
+        self.rng = checkpoint.rng.clone();
+        self.resume_state = Some(checkpoint);
+        true
+    }
 
-                let parents: (&Chromosome,&Chromosome) = self.select_parents();                
-        
-                let mut new_individuals: (Chromosome,Chromosome) = self.crossover(parents);
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> { 
         
-                new_individuals.0.fitness = fitness_function(&new_individuals.0.values);
-                new_individuals.1.fitness = fitness_function(&new_individuals.1.values);
-                
-                self.population.push(new_individuals.0);
-                self.population.push(new_individuals.1);
-                    
+        let start: Instant = Instant::now();
+
+        let pool: ThreadPool = thread_pool(self.threads)?;
+
+        let mut tracker: RunTracker = match self.resume_state.take() {
+            Some(checkpoint) => {
+                println!("resuming from generation {}", checkpoint.generation);
+                RunTracker::resume(&checkpoint, &self.bounds, self.minimization, self.hall_of_fame.as_ref())
+            },
+            None => {
+                let evaluations: usize = self.initialize(&pool, objective)?;
+
+                RunTracker::new(start, &self.population, &self.bounds, evaluations, self.minimization, self.hall_of_fame.as_ref(), observer)?
+            },
+        };
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(reason) = tracker.next_iteration(&self.termination, self.max_generations, observer) {
+                break reason;
             }
 
-            let p_newsize: usize = self.population.len();
-            let count: usize = p_newsize - p_size;
-            p_size = p_newsize;
-            //mutate and calculate fitness of each individual of new population
-            for id in 5..p_size {
-                
-                self.population[id].mutation(self.mutation_rate, &self.bounds);
-                
-                self.population[id].fitness = fitness_function(&self.population[id].values);
-            }               
-
-            quicksort_by(&mut self.population, GA::compare);
-
-            //get the best individual
-            if self.minimization {
-        
-                best = self.population.first().expect("The population vec is empty").clone();
-        
-                for k in 0..count {
-                    self.population.remove(p_size-1-k);
+            tracker.evaluations += self.next_generation(&pool, objective);
+
+            tracker.end_iteration(&self.population, &self.bounds, observer);
+
+            if let Some(policy) = &self.restart {
+                let diversity: f64 = tracker.history.generations.last().map_or(f64::NAN, |s| s.diversity);
+                if diversity < policy.min_diversity {
+                    let elite: Vec<Chromosome> = tracker.hall_of_fame.as_ref().map_or_else(|| vec![tracker.best.clone()], |h| h.members.clone());
+                    let (reseeded, evaluations): (usize, usize) = policy.reseed(&pool, &mut self.population, &elite, &self.bounds, objective, &mut self.rng);
+                    sort_population(&mut self.population, self.minimization);
+                    let restart: Restart = Restart { generation: tracker.iteration, diversity, reseeded, evaluations };
+                    tracker.restarted(restart, &self.population, observer);
                 }
             }
-            else {
-        
-                best = self.population.last().expect("The population vec is empty").clone();
-        
-                for k in 0..count {
-                    self.population.remove(k);
+
+            if let Some(local_search) = &self.local_search {
+                if local_search.interval.is_some_and(|i| i > 0 && tracker.iteration.is_multiple_of(i)) {
+                    let refinement: Refinement = local_search.refine(&pool, &mut self.population, tracker.iteration, objective, &self.bounds, self.minimization);
+                    tracker.refined(refinement, &self.population, observer);
                 }
             }
             
-            solutions.push(best.to_string());            
-            println!("current best is {:?}", best);             
 
-            i += 1;
+            if let Some(config) = &self.checkpoint {
+                if config.interval > 0 && tracker.iteration.is_multiple_of(config.interval) {
+                    let checkpoint: Checkpoint = Checkpoint {
+                        config_hash: self.config_hash,
+                        generation: tracker.iteration,
+                        evaluations: tracker.evaluations,
+                        elapsed: tracker.elapsed().as_secs_f64(),
+                        generations_without_improvement: tracker.progress.generations_without_improvement,
+                        best: tracker.best.clone(),
+                        population: self.population.clone(),
+                        seed: self.seed,
+                        rng: self.rng.clone(),
+                        history: tracker.history.clone(),
+                        hall_of_fame: tracker.hall_of_fame.as_ref().map_or(vec![], |h| h.members.clone()),
+                    };
+                    if let Err(e) = save_checkpoint(&checkpoint, &config.path) {
+                        println!("Could not save the checkpoint: {:?}", e);
+                    }
+                }
+            }
+        };
+
+        //the final population is refined unless it was just refined or the run was cancelled
+        if let Some(local_search) = &self.local_search {
+            let refined: bool = tracker.history.refinements.last().is_some_and(|r| r.generation == tracker.iteration);
+            if !refined && stop_reason != StopReason::Cancelled {
+                let refinement: Refinement = local_search.refine(&pool, &mut self.population, tracker.iteration, objective, &self.bounds, self.minimization);
+                tracker.refined(refinement, &self.population, observer);
+            }
+            tracker.covariance = local_search.covariance(objective, &tracker.best, &self.bounds);
         }
         
-        GA::to_disk::<String>(Path::new(&String::from("./src/ode/tests/ga_iterations.txt")), solutions).unwrap();
-        Ok(best)
-    }  
-
-    pub fn to_disk<P: AsRef<Path>>(path: &Path, data: Vec<String>) -> anyhow::Result<(),Error> {
-        let mut file: File = match File::create(path) {
-            Ok(f) => f,
-            Err(e) => return Err(e.into()),
-        };
-        for v in &data{                                                                                                                                                                  
-            write!(file, "{ }", v)?;                                                                                                                             
+
+        let mut result: OptimizationResult = tracker.finish(stop_reason, observer);
+        if let Some(niching) = &self.niching {
+            result.niches = niching.niches(&self.population, &self.bounds, self.minimization);
         }
-        Ok(())
-    }  
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::observer::ConsoleObserver;
+
+    fn run(seed: u64) -> OptimizationResult {
+        let metadata: GA_Metadata = GA_Metadata {
+            population_size: 20, crossover_rate: 0.5, mutation_rate: 0.5, max_iterations: 15, seed: Some(seed), threads: Some(2),
+            ..Default::default()
+        };
+        let bounds: Vec<Bound> = vec![Bound::new(String::from("x"), -5.0, 5.0), Bound::new(String::from("y"), -5.0, 5.0)];
+        let objective = (|| (), |_: &mut (), values: &[f64]| values.iter().map(|v| (v - 1.0)*(v - 1.0)).sum::<f64>());
+
+        GA::from_metadata(&metadata, bounds, true).optimize(&objective, &mut ConsoleObserver).unwrap()
+    }
+
+    #[test]
+    fn same_seed_gives_same_best() {
+        let (first, second) = (run(3), run(3));
+        assert_eq!(first.best.values(), second.best.values());
+        assert_eq!(first.best.fitness, second.best.fitness);
+        assert_eq!(first.evaluations, second.evaluations);
+    }
+}
@@ -1,7 +1,23 @@
-use std::{fs::File, io::{BufReader, BufWriter, Error}, path::Path};
+use std::{fs::File, io::{BufReader, BufWriter, Error, ErrorKind}, path::Path};
 
 use serde::{Deserialize, Serialize};
 
+use super::cache::CacheConfig;
+use super::checkpoint::CheckpointConfig;
+use super::constraints::Constraints;
+use super::crossover::Crossover;
+use super::hall_of_fame::HallOfFameConfig;
+use super::initialization::Initialization;
+use super::islands::Islands;
+use super::local_search::LocalSearch;
+use super::mutation::Mutation;
+use super::niching::Niching;
+use super::optimizer::Algorithm;
+use super::replacement::Replacement;
+use super::restart::RestartPolicy;
+use super::selection::Selection;
+use super::termination::Termination;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GA_Metadata {
     pub name: String,
@@ -12,6 +28,44 @@ pub struct GA_Metadata {
     pub crossover_rate: f64,
     pub mutation_rate: f64,
     pub max_iterations: usize,
+    #[serde(default)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub crossover: Crossover,
+    #[serde(default)]
+    pub mutation: Mutation,
+    #[serde(default)]
+    pub replacement: Replacement,
+    #[serde(default)]
+    pub elitism: usize,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub threads: Option<usize>,
+    #[serde(default)]
+    pub termination: Termination,
+    #[serde(default)]
+    pub checkpoint: Option<CheckpointConfig>,
+    #[serde(default)]
+    pub history_file: Option<String>, //statistics of each generation, saved as JSON (.json) or CSV
+    #[serde(default)]
+    pub algorithm: Algorithm,
+    #[serde(default)]
+    pub local_search: Option<LocalSearch>, //local refinement of the best individuals of the GA
+    #[serde(default)]
+    pub initialization: Initialization, //initial population of the population-based algorithms
+    #[serde(default)]
+    pub islands: Option<Islands>, //divides the population of the GA in islands
+    #[serde(default)]
+    pub constraints: Option<Constraints>, //relations between the parameters besides their bounds
+    #[serde(default)]
+    pub restart: Option<RestartPolicy>, //reseeds the population of the GA when it loses its diversity
+    #[serde(default)]
+    pub hall_of_fame: Option<HallOfFameConfig>,
+    #[serde(default)]
+    pub cache: Option<CacheConfig>, //fitness of the individuals already evaluated
+    #[serde(default)]
+    pub niching: Option<Niching>, //fitness sharing, clearing or crowding in the GA
 }
 
 //initial condition 
@@ -30,12 +84,35 @@ impl GA_Argument {
     }
 }
 
+//space in which a parameter is searched, the logarithmic scales need a positive interval
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Scale {
+    #[default]
+    Linear,
+    Log10,
+    Ln,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    #[default]
+    Continuous,
+    //the value is rounded before the evaluation and the reporting
+    Integer,
+}
+
 //parameters to be adjusted 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct Bound {
     pub name: String,
     pub min: f64,
     pub max: f64,
+    #[serde(default)]
+    pub scale: Scale,
+    #[serde(default)]
+    pub kind: Kind,
 }
 
 impl Bound {
@@ -43,9 +120,86 @@ impl Bound {
         Self {
             name: name, 
             min: min, 
-            max: max
+            max: max,
+            scale: Scale::Linear,
+            kind: Kind::Continuous,
+        }
+    }
+
+    //interval of the natural values, integers get half a unit at each side so every integer is equally likely
+    fn interval(&self) -> (f64, f64) {
+        match self.kind {
+            Kind::Continuous => (self.min, self.max),
+            Kind::Integer => (self.min.ceil() - 0.5, self.max.floor() + 0.5),
+        }
+    }
+
+    //value of the search space of a natural value
+    pub fn encode(&self, value: f64) -> f64 {
+        match self.scale {
+            Scale::Linear => value,
+            Scale::Log10 => value.log10(),
+            Scale::Ln => value.ln(),
         }
     }
+
+    //natural value of a value of the search space
+    pub fn decode(&self, value: f64) -> f64 {
+        let natural: f64 = match self.scale {
+            Scale::Linear => value,
+            Scale::Log10 => 10f64.powf(value),
+            Scale::Ln => value.exp(),
+        };
+        self.restrict(natural)
+    }
+
+    //natural value inside the bound, rounded if the parameter is an integer
+    pub fn restrict(&self, value: f64) -> f64 {
+        match self.kind {
+            Kind::Continuous => self.clamp(value),
+            Kind::Integer => value.round().clamp(self.min.ceil(), self.max.floor()),
+        }
+    }
+
+    //derivative of the natural value with respect to the value of the search space
+    pub fn derivative(&self, value: f64) -> f64 {
+        match self.scale {
+            Scale::Linear => 1.0,
+            Scale::Log10 => 10f64.powf(value)*std::f64::consts::LN_10,
+            Scale::Ln => value.exp(),
+        }
+    }
+
+    //linear and continuous bound of the search space, where the optimizers work
+    pub fn encoded(&self) -> Bound {
+        let (min, max) = self.interval();
+        Bound::new(self.name.clone(), self.encode(min), self.encode(max))
+    }
+
+    //the interval must not be inverted, a logarithmic scale needs a positive minimum and an integer parameter
+    //must have an integer inside it
+    pub fn validate(&self) -> Result<(),String> {
+        if self.min.is_nan() || self.max.is_nan() || self.min > self.max {
+            return Err(format!("the bound {} has a minimum greater than its maximum", self.name));
+        }
+        if self.scale != Scale::Linear && self.min <= 0.0 {
+            return Err(format!("the bound {} has a logarithmic scale and a non positive minimum", self.name));
+        }
+        if self.kind == Kind::Integer && self.min.ceil() > self.max.floor() {
+            return Err(format!("the integer bound {} has no integer between {} and {}", self.name, self.min, self.max));
+        }
+        Ok(())
+    }
+
+    pub fn clamp(&self, value: f64) -> f64 {
+        if value < self.min {
+            return self.min;
+        }
+        if value > self.max {
+            return self.max;
+        }
+        value
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -74,10 +228,59 @@ pub fn load_json<P: AsRef<Path>>(path: P) -> anyhow::Result<ConfigData,Error> {
         Err(e) => return Err(e.into()),
     };
     let reader: BufReader<File> = BufReader::new(file);
-    let json: Result<ConfigData, serde_json::Error> = serde_json::from_reader(reader);
-    
-    match json {
-        Ok(f) => Ok(f),
-        Err(e) => return Err(e.into()),
+    let config: ConfigData = serde_json::from_reader(reader)?;
+
+    if let Some(e) = config.bounds.iter().find_map(|b| b.validate().err()) {
+        return Err(Error::new(ErrorKind::InvalidData, e));
     }
-}
\ No newline at end of file
+    config.metadata.crossover.validate().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    Ok(config)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bound(min: f64, max: f64, scale: Scale, kind: Kind) -> Bound {
+        Bound { name: String::from("k"), min, max, scale, kind }
+    }
+
+    #[test]
+    fn logarithmic_scales_round_trip() {
+        for scale in [Scale::Log10, Scale::Ln] {
+            let b: Bound = bound(1e-3, 1e3, scale, Kind::Continuous);
+            for value in [1e-3, 0.05, 1.0, 42.0, 1e3] {
+                assert!((b.decode(b.encode(value)) - value).abs() <= 1e-12*value);
+            }
+        }
+
+        let encoded: Bound = bound(1e-3, 1e3, Scale::Log10, Kind::Continuous).encoded();
+        assert!((encoded.min + 3.0).abs() < 1e-12 && (encoded.max - 3.0).abs() < 1e-12);
+        let encoded: Bound = bound(1.0, std::f64::consts::E, Scale::Ln, Kind::Continuous).encoded();
+        assert!(encoded.min.abs() < 1e-12 && (encoded.max - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn integers_are_rounded_inside_the_bound() {
+        let b: Bound = bound(0.5, 4.5, Scale::Linear, Kind::Integer);
+        assert_eq!(b.restrict(2.4), 2.0);
+        assert_eq!(b.restrict(2.6), 3.0);
+        assert_eq!(b.restrict(0.2), 1.0);
+        assert_eq!(b.restrict(7.0), 4.0);
+
+        //half a unit at each side of the integers 1 to 4
+        let encoded: Bound = b.encoded();
+        assert_eq!((encoded.min, encoded.max), (0.5, 4.5));
+        assert_eq!(b.decode(encoded.min), 1.0);
+    }
+
+    #[test]
+    fn invalid_bounds_are_rejected() {
+        assert!(bound(1.0, 2.0, Scale::Linear, Kind::Continuous).validate().is_ok());
+        assert!(bound(2.0, 1.0, Scale::Linear, Kind::Continuous).validate().is_err());
+        assert!(bound(f64::NAN, 1.0, Scale::Linear, Kind::Continuous).validate().is_err());
+        assert!(bound(1.2, 1.8, Scale::Linear, Kind::Integer).validate().is_err());
+        assert!(bound(0.0, 10.0, Scale::Log10, Kind::Continuous).validate().is_err());
+        assert!(bound(-1.0, 10.0, Scale::Ln, Kind::Continuous).validate().is_err());
+        assert!(bound(0.1, 10.0, Scale::Log10, Kind::Continuous).validate().is_ok());
+    }
+}
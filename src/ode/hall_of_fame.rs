@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use super::diversity::{distance, normalize};
+use super::ga::{is_fitter, sort_population, Chromosome};
+use super::ga_json::Bound;
+
+//how many distinct solutions are kept and where they are written at the end of the run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HallOfFameConfig {
+    pub size: usize,
+    //normalized distance (0 to 1, as the diversity) below which two solutions are the same one
+    pub min_distance: f64,
+    pub path: Option<String>, //CSV file, None does not write it
+}
+
+impl Default for HallOfFameConfig {
+    fn default() -> Self {
+        Self {
+            size: 10,
+            min_distance: 0.01,
+            path: None,
+        }
+    }
+}
+
+//best distinct solutions seen during a run, from the best to the worst
+#[derive(Debug, Clone)]
+pub struct HallOfFame {
+    config: HallOfFameConfig,
+    bounds: Vec<Bound>,
+    minimization: bool,
+    pub members: Vec<Chromosome>,
+}
+
+impl HallOfFame {
+
+    pub fn new(config: &HallOfFameConfig, bounds: &[Bound], minimization: bool) -> Self {
+        Self {
+            config: config.clone(),
+            bounds: bounds.to_vec(),
+            minimization,
+            members: vec![],
+        }
+    }
+
+    //an individual enters if no member close to it is as good, the worse members close to it leave
+    pub fn update(&mut self, population: &[Chromosome]) {
+
+        let scale: f64 = (self.bounds.len() as f64).sqrt().max(1.0);
+
+        for c in population.iter().filter(|c| c.fitness.is_finite()) {
+
+            let point: Vec<f64> = normalize(c.values(), &self.bounds);
+            let close: Vec<bool> = self.members
+                .iter()
+                .map(|m| distance(&normalize(m.values(), &self.bounds), &point)/scale < self.config.min_distance)
+                .collect();
+
+            let dominated: bool = self.members
+                .iter()
+                .zip(close.iter())
+                .any(|(m, close)| *close && !is_fitter(c, m, self.minimization));
+            if dominated {
+                continue;
+            }
+
+            let mut i: usize = 0;
+            self.members.retain(|_| { i += 1; !close[i - 1] });
+            self.members.push(c.clone());
+            sort_population(&mut self.members, self.minimization);
+            self.members.truncate(self.config.size);
+        }
+    }
+}
+
+//position, values of the parameters and fitness of each solution
+pub fn save_hall_of_fame<P: AsRef<Path>>(path: P, members: &[Chromosome], bounds: &[Bound]) -> anyhow::Result<(),Error> {
+
+    let mut writer = csv::Writer::from_path(path)?;
+
+    let mut header: Vec<String> = vec![String::from("rank")];
+    header.extend(bounds.iter().map(|b| b.name.clone()));
+    header.push(String::from("fitness"));
+    writer.write_record(&header)?;
+
+    for (rank, member) in members.iter().enumerate() {
+        let mut record: Vec<String> = vec![(rank + 1).to_string()];
+        record.extend(member.values().iter().map(|v| v.to_string()));
+        record.push(member.fitness.to_string());
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
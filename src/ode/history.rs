@@ -0,0 +1,194 @@
+use std::{fs::File, io::{BufWriter, Error}, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::diversity::{entropy, gene_variance, mean_pairwise_distance};
+use super::ga::Chromosome;
+use super::ga_json::Bound;
+
+//statistics of the population at the end of a generation (generation 0 is the initial population)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub worst: f64,
+    pub diversity: f64,
+    #[serde(default)]
+    pub entropy: f64,
+    #[serde(default, with = "values")]
+    pub gene_variance: Vec<f64>, //variance of each normalized gene
+    pub evaluations: usize,
+    pub elapsed: f64, //seconds since the start of the run
+}
+
+//a list of values written as a single field separated by spaces, so that it fits in a CSV column
+mod values {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&values.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(" "))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+        String::deserialize(deserializer)?
+            .split_whitespace()
+            .map(|v| v.parse::<f64>().map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+impl GenerationStats {
+
+    pub fn from_population(generation: usize, population: &[Chromosome], bounds: &[Bound], evaluations: usize, elapsed: f64, minimization: bool) -> Self {
+
+        let mut fitness: Vec<f64> = population.iter().map(|c| c.fitness).collect();
+        fitness.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        if !minimization {
+            fitness.reverse();
+        }
+
+        let n: usize = fitness.len();
+        let median: f64 = match n {
+            0 => f64::NAN,
+            _ if n % 2 == 1 => fitness[n/2],
+            _ => 0.5*(fitness[n/2 - 1] + fitness[n/2]),
+        };
+
+        Self {
+            generation,
+            best: fitness.first().copied().unwrap_or(f64::NAN),
+            mean: fitness.iter().sum::<f64>() / n as f64,
+            median,
+            worst: fitness.last().copied().unwrap_or(f64::NAN),
+            diversity: mean_pairwise_distance(population, bounds),
+            entropy: entropy(population, bounds),
+            gene_variance: gene_variance(population, bounds),
+            evaluations,
+            elapsed,
+        }
+    }
+}
+
+//local search applied to the best individuals after a generation
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Refinement {
+    pub generation: usize,
+    pub individuals: usize,
+    pub before: f64, //best fitness before the local search
+    pub after: f64,
+    pub evaluations: usize,
+}
+
+//part of the population sampled again after a loss of diversity
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Restart {
+    pub generation: usize,
+    pub diversity: f64, //diversity that triggered the restart
+    pub reseeded: usize, //new individuals
+    pub evaluations: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct History {
+    pub generations: Vec<GenerationStats>,
+    #[serde(default)]
+    pub refinements: Vec<Refinement>,
+    #[serde(default)]
+    pub restarts: Vec<Restart>,
+}
+
+impl History {
+
+    pub fn push(&mut self, stats: GenerationStats) {
+        self.generations.push(stats);
+    }
+
+    //writes the history as JSON if the file extension is .json, otherwise as CSV
+    //in CSV the refinements and the restarts go to other files with the suffixes _refinements and _restarts
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<(),Error> {
+
+        let is_json: bool = path.as_ref().extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+        let file: File = File::create(&path)?;
+
+        if is_json {
+            serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        }
+        else {
+            let mut writer = csv::Writer::from_writer(file);
+            for stats in self.generations.iter() {
+                writer.serialize(stats)?;
+            }
+            writer.flush()?;
+
+            let stem: String = path.as_ref().file_stem().map_or(String::new(), |s| s.to_string_lossy().to_string());
+
+            if !self.refinements.is_empty() {
+                let mut writer = csv::Writer::from_path(path.as_ref().with_file_name(format!("{}_refinements.csv", stem)))?;
+                for refinement in self.refinements.iter() {
+                    writer.serialize(refinement)?;
+                }
+                writer.flush()?;
+            }
+
+            if !self.restarts.is_empty() {
+                let mut writer = csv::Writer::from_path(path.as_ref().with_file_name(format!("{}_restarts.csv", stem)))?;
+                for restart in self.restarts.iter() {
+                    writer.serialize(restart)?;
+                }
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> History {
+        let mut history: History = History::default();
+        for generation in 0..3 {
+            history.push(GenerationStats {
+                generation, best: 1.0/(generation + 1) as f64, mean: 2.0, median: 1.5, worst: 3.0, diversity: 0.5, entropy: 0.25,
+                gene_variance: vec![0.1, 0.2], evaluations: 10*(generation + 1), elapsed: 0.125,
+            });
+        }
+        history.restarts.push(Restart { generation: 2, diversity: 0.01, reseeded: 4, evaluations: 4 });
+        history
+    }
+
+    fn path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ga_test_history_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn json_has_every_generation() {
+        let path: std::path::PathBuf = path("run.json");
+        history().save(&path).unwrap();
+        let saved: History = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(saved, history());
+    }
+
+    #[test]
+    fn csv_has_one_row_per_generation() {
+        let path: std::path::PathBuf = path("run.csv");
+        let restarts: std::path::PathBuf = path.with_file_name(format!("{}_restarts.csv", path.file_stem().unwrap().to_string_lossy()));
+        history().save(&path).unwrap();
+        let generations: String = std::fs::read_to_string(&path).unwrap();
+        let restarts_csv: String = std::fs::read_to_string(&restarts).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&restarts);
+
+        let lines: Vec<&str> = generations.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "generation,best,mean,median,worst,diversity,entropy,gene_variance,evaluations,elapsed");
+        assert_eq!(lines[1], "0,1.0,2.0,1.5,3.0,0.5,0.25,0.1 0.2,10,0.125");
+        assert!(lines[3].starts_with("2,"));
+
+        assert_eq!(restarts_csv.lines().collect::<Vec<&str>>(), vec!["generation,diversity,reseeded,evaluations", "2,0.01,4,4"]);
+    }
+}
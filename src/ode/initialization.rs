@@ -0,0 +1,306 @@
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{anyhow, Error};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::checkpoint::load_checkpoint;
+use super::encoding::encode;
+use super::ga::{random_population, Chromosome};
+use super::ga_json::Bound;
+
+//how the points of the initial population are spread over the bounds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampling {
+    //each gene drawn independently
+    #[default]
+    Uniform,
+    //each bound divided in as many intervals as individuals, every interval is used once
+    LatinHypercube,
+    //low-discrepancy sequences, randomized by the seed
+    Sobol,
+    Halton,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Initialization {
+    pub sampling: Sampling,
+    //individuals included in the initial population: a CSV file with a column for each parameter,
+    //or a checkpoint (.json) of a previous run whose best individual is used
+    pub seed_file: Option<String>,
+    //bounds of the parameters, used to encode the values of the CSV file into the search space
+    #[serde(skip)]
+    pub encoding: Vec<Bound>,
+}
+
+impl Initialization {
+
+    //the seed individuals followed by sampled ones until the population has `size` individuals
+    pub fn population<R: Rng + ?Sized>(&self, bounds: &[Bound], size: usize, rng: &mut R) -> Result<Vec<Chromosome>,()> {
+
+        let mut population: Vec<Chromosome> = match &self.seed_file {
+            Some(path) => match load_individuals(path, bounds, &self.encoding) {
+                Ok(individuals) => individuals,
+                Err(e) => { println!("Could not read the individuals of {}: {}", path, e); return Err(()); },
+            },
+            None => vec![],
+        };
+        population.truncate(size);
+
+        let count: usize = size - population.len();
+        population.append(&mut sample(self.sampling, bounds, count, rng));
+        Ok(population)
+    }
+}
+
+pub fn sample<R: Rng + ?Sized>(sampling: Sampling, bounds: &[Bound], size: usize, rng: &mut R) -> Vec<Chromosome> {
+
+    let points: Vec<Vec<f64>> = match sampling {
+        Sampling::Uniform => return random_population(bounds, size, rng),
+        Sampling::LatinHypercube => latin_hypercube(bounds.len(), size, rng),
+        Sampling::Sobol => sobol(bounds.len(), size, rng),
+        Sampling::Halton => halton(bounds.len(), size, rng),
+    };
+
+    points
+        .into_iter()
+        .map(|u| Chromosome::new(bounds.iter().zip(u.iter()).map(|(b, u)| b.min + u*(b.max - b.min)).collect()))
+        .collect()
+}
+
+//points of the unit hypercube
+fn latin_hypercube<R: Rng + ?Sized>(dimension: usize, size: usize, rng: &mut R) -> Vec<Vec<f64>> {
+
+    let mut points: Vec<Vec<f64>> = vec![vec![0.0; dimension]; size];
+
+    for j in 0..dimension {
+        let mut strata: Vec<usize> = (0..size).collect();
+        strata.shuffle(rng);
+        for (point, stratum) in points.iter_mut().zip(strata) {
+            point[j] = (stratum as f64 + rng.gen_range(0.0..1.0))/size as f64;
+        }
+    }
+    points
+}
+
+//degree s, coefficients a and initial direction numbers m of the primitive polynomials of the dimensions
+//2 to 21 (Joe and Kuo, new-joe-kuo-6.21201), the first dimension uses the van der Corput sequence
+const SOBOL_POLYNOMIALS: [(u32, u32, &[u32]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+const SOBOL_BITS: u32 = 32;
+
+//direction numbers v[k] = m[k]*2^(32-k-1) of one dimension
+fn direction_numbers(dimension: usize) -> Vec<u32> {
+
+    if dimension == 0 {
+        return (0..SOBOL_BITS).map(|k| 1 << (SOBOL_BITS - 1 - k)).collect();
+    }
+
+    let (s, a, m) = SOBOL_POLYNOMIALS[dimension - 1];
+    let s: usize = s as usize;
+    let mut v: Vec<u32> = vec![0; SOBOL_BITS as usize];
+
+    for k in 0..SOBOL_BITS as usize {
+        if k < s {
+            v[k] = m[k] << (SOBOL_BITS as usize - 1 - k);
+        }
+        else {
+            let mut value: u32 = v[k - s] ^ (v[k - s] >> s);
+            for i in 1..s {
+                if (a >> (s - 1 - i)) & 1 == 1 {
+                    value ^= v[k - i];
+                }
+            }
+            v[k] = value;
+        }
+    }
+    v
+}
+
+//Sobol points (Gray code order, without the origin) with a random digital shift,
+//the dimensions beyond the table use the Halton sequence
+fn sobol<R: Rng + ?Sized>(dimension: usize, size: usize, rng: &mut R) -> Vec<Vec<f64>> {
+
+    let sobol_dimension: usize = dimension.min(SOBOL_POLYNOMIALS.len() + 1);
+    if sobol_dimension < dimension {
+        println!("Sobol sequences are available up to {} parameters, the other ones use the Halton sequence", sobol_dimension);
+    }
+
+    let directions: Vec<Vec<u32>> = (0..sobol_dimension).map(direction_numbers).collect();
+    let shifts: Vec<u32> = (0..sobol_dimension).map(|_| rng.gen()).collect();
+    let extra: Vec<Vec<f64>> = halton(dimension - sobol_dimension, size, rng);
+
+    let mut x: Vec<u32> = vec![0; sobol_dimension];
+    let mut points: Vec<Vec<f64>> = vec![];
+
+    for (i, extra) in extra.iter().enumerate() {
+        //the bit that changes between the Gray codes of i and i + 1
+        let c: usize = (!i).trailing_zeros() as usize;
+        for (j, v) in directions.iter().enumerate() {
+            x[j] ^= v[c.min(SOBOL_BITS as usize - 1)];
+        }
+
+        let mut point: Vec<f64> = x.iter()
+            .zip(shifts.iter())
+            .map(|(x, s)| (x ^ s) as f64/2f64.powi(SOBOL_BITS as i32))
+            .collect();
+        point.extend_from_slice(extra);
+        points.push(point);
+    }
+    points
+}
+
+fn primes(count: usize) -> Vec<u64> {
+    let mut primes: Vec<u64> = vec![];
+    let mut candidate: u64 = 2;
+    while primes.len() < count {
+        if primes.iter().all(|p| !candidate.is_multiple_of(*p)) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+fn radical_inverse(mut i: u64, base: u64) -> f64 {
+    let mut result: f64 = 0.0;
+    let mut f: f64 = 1.0/base as f64;
+    while i > 0 {
+        result += f*(i % base) as f64;
+        i /= base;
+        f /= base as f64;
+    }
+    result
+}
+
+//Halton points with a random shift modulo 1 (Cranley-Patterson rotation)
+fn halton<R: Rng + ?Sized>(dimension: usize, size: usize, rng: &mut R) -> Vec<Vec<f64>> {
+
+    let bases: Vec<u64> = primes(dimension);
+    let shifts: Vec<f64> = (0..dimension).map(|_| rng.gen_range(0.0..1.0)).collect();
+
+    (1..=size as u64)
+        .map(|i| bases.iter()
+            .zip(shifts.iter())
+            .map(|(b, s)| (radical_inverse(i, *b) + s).fract())
+            .collect())
+        .collect()
+}
+
+//individuals of a CSV file (the header has the names of the parameters) or the best individual of a checkpoint,
+//the values of the CSV file are encoded with `encoding` if it is not empty, then kept inside the bounds
+pub fn load_individuals<P: AsRef<Path>>(path: P, bounds: &[Bound], encoding: &[Bound]) -> anyhow::Result<Vec<Chromosome>,Error> {
+
+    let is_json: bool = path.as_ref().extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+
+    let individuals: Vec<Vec<f64>> = if is_json {
+        vec![load_checkpoint(&path)?.best.get_values()]
+    }
+    else {
+        let mut reader = csv::Reader::from_reader(File::open(&path)?);
+        let headers: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_string()).collect();
+
+        let columns: Vec<usize> = bounds.iter()
+            .map(|b| headers.iter().position(|h| *h == b.name).ok_or_else(|| anyhow!("the column {} is missing", b.name)))
+            .collect::<Result<_,_>>()?;
+
+        let mut individuals: Vec<Vec<f64>> = vec![];
+        for record in reader.records() {
+            let record = record?;
+            let values: Vec<f64> = columns.iter()
+                .map(|c| record.get(*c).unwrap_or("").trim().parse::<f64>())
+                .collect::<Result<_,_>>()?;
+            individuals.push(if encoding.is_empty() { values } else { encode(encoding, &values) });
+        }
+        individuals
+    };
+
+    if individuals.iter().any(|values| values.len() != bounds.len()) {
+        return Err(anyhow!("the individuals of {} do not have a value for each bound", path.as_ref().display()));
+    }
+
+    Ok(individuals
+        .into_iter()
+        .map(|values| Chromosome::new(values.iter().zip(bounds.iter()).map(|(v, b)| b.clamp(*v)).collect()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn in_unit_cube(points: &[Vec<f64>], dimension: usize) -> bool {
+        points.iter().all(|p| p.len() == dimension && p.iter().all(|u| (0.0..1.0).contains(u)))
+    }
+
+    #[test]
+    fn points_stay_in_the_unit_cube() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(1);
+        assert!(in_unit_cube(&latin_hypercube(3, 100, &mut rng), 3));
+        assert!(in_unit_cube(&halton(3, 100, &mut rng), 3));
+        assert!(in_unit_cube(&sobol(3, 100, &mut rng), 3));
+        //the dimensions beyond the Sobol table use the Halton sequence
+        assert!(in_unit_cube(&sobol(25, 100, &mut rng), 25));
+    }
+
+    #[test]
+    fn latin_hypercube_uses_every_stratum_once() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(2);
+        let size: usize = 50;
+        let points: Vec<Vec<f64>> = latin_hypercube(4, size, &mut rng);
+        for j in 0..4 {
+            let mut strata: Vec<usize> = points.iter().map(|p| (p[j]*size as f64).floor() as usize).collect();
+            strata.sort();
+            assert_eq!(strata, (0..size).collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn sobol_without_shift_matches_joe_kuo() {
+        //the zero generator gives a zero digital shift
+        let points: Vec<Vec<f64>> = sobol(3, 7, &mut StepRng::new(0, 0));
+        let expected: [[f64; 3]; 7] = [
+            [0.5, 0.5, 0.5], [0.75, 0.25, 0.25], [0.25, 0.75, 0.75], [0.375, 0.375, 0.625],
+            [0.875, 0.875, 0.125], [0.625, 0.125, 0.875], [0.125, 0.625, 0.375],
+        ];
+        for (point, expected) in points.iter().zip(expected.iter()) {
+            assert_eq!(point.as_slice(), expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn halton_without_shift_is_the_radical_inverse() {
+        let points: Vec<Vec<f64>> = halton(2, 3, &mut StepRng::new(0, 0));
+        assert_eq!(points[0], vec![0.5, 1.0/3.0]);
+        assert_eq!(points[1], vec![0.25, 2.0/3.0]);
+        assert_eq!(points[2], vec![0.75, 1.0/9.0]);
+    }
+}
@@ -0,0 +1,216 @@
+use std::time::Instant;
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{sort_population, Chromosome, GA};
+use super::ga_json::{Bound, GA_Metadata};
+use super::hall_of_fame::HallOfFameConfig;
+use super::niching::Niching;
+use super::history::History;
+use super::objective::{thread_pool, Objective};
+use super::observer::Observer;
+use super::optimizer::{run_seed, OptimizationResult, Optimizer, RunTracker};
+use super::termination::{StopReason, Termination};
+
+//islands that receive the emigrants of each island
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationTopology {
+    //the next island
+    #[default]
+    Ring,
+    //every other island
+    FullyConnected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Islands {
+    pub count: usize,
+    pub interval: usize, //generations between migrations, 0 never migrates
+    pub migrants: usize, //best individuals sent by each island, they replace the worst ones of the destination
+    pub topology: MigrationTopology,
+    pub parallel: bool, //evolves the islands on separate threads
+}
+
+impl Default for Islands {
+    fn default() -> Self {
+        Self {
+            count: 4,
+            interval: 10,
+            migrants: 2,
+            topology: MigrationTopology::default(),
+            parallel: false,
+        }
+    }
+}
+
+//GA with the population divided in islands that evolve independently and exchange individuals
+//the population size of the configuration is divided among the islands
+#[derive(Debug, Clone)]
+pub struct IslandModel {
+    config: Islands,
+    islands: Vec<GA>,
+    bounds: Vec<Bound>,
+    minimization: bool,
+    max_generations: usize,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    niching: Option<Niching>, //the niches are counted in the population of every island
+    threads: Option<usize>,
+    seed: u64,
+    history: History,
+}
+
+impl IslandModel {
+
+    pub fn from_metadata(metadata: &GA_Metadata, config: Islands, bounds: Vec<Bound>, is_min: bool) -> Self {
+
+        let seed: u64 = run_seed(metadata);
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(seed);
+
+        let count: usize = config.count.max(1);
+        let mut island_metadata: GA_Metadata = metadata.clone();
+        island_metadata.population_size = (metadata.population_size/count).max(2);
+
+        let islands: Vec<GA> = (0..count)
+            .map(|_| GA::from_metadata(&island_metadata, bounds.clone(), is_min).with_seed(rng.next_u64()))
+            .collect();
+
+        Self {
+            config,
+            islands,
+            bounds,
+            minimization: is_min,
+            max_generations: metadata.max_iterations,
+            termination: metadata.termination.clone(),
+            hall_of_fame: metadata.hall_of_fame.clone(),
+            niching: metadata.niching.clone(),
+            threads: metadata.threads,
+            seed,
+            history: History::default(),
+        }
+    }
+
+    fn population(&self) -> Vec<Chromosome> {
+        self.islands.iter().flat_map(|ga| ga.population.iter().cloned()).collect()
+    }
+
+    //the best individuals of every island replace the worst ones of its destinations
+    fn migrate(&mut self) {
+
+        let n: usize = self.islands.len();
+        if n < 2 {
+            return;
+        }
+
+        let emigrants: Vec<Vec<Chromosome>> = self.islands
+            .iter()
+            .map(|ga| ga.population.iter().take(self.config.migrants).cloned().collect())
+            .collect();
+
+        for (i, island) in self.islands.iter_mut().enumerate() {
+
+            let immigrants: Vec<Chromosome> = match self.config.topology {
+                MigrationTopology::Ring => emigrants[(i + n - 1) % n].clone(),
+                MigrationTopology::FullyConnected => (0..n).filter(|j| *j != i).flat_map(|j| emigrants[j].iter().cloned()).collect(),
+            };
+
+            //the population is sorted, the worst individuals are at the end
+            let keep: usize = island.population.len().saturating_sub(immigrants.len());
+            let size: usize = island.population.len();
+            island.population.truncate(keep);
+            island.population.extend(immigrants.into_iter().take(size - keep));
+            sort_population(&mut island.population, self.minimization);
+        }
+    }
+}
+
+impl<O: Objective> Optimizer<O> for IslandModel {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> {
+
+        let start: Instant = Instant::now();
+
+        let pool: ThreadPool = thread_pool(self.threads)?;
+
+        let mut evaluations: usize = 0;
+        for ga in self.islands.iter_mut() {
+            evaluations += ga.initialize(&pool, objective)?;
+        }
+
+        let mut tracker: RunTracker = RunTracker::new(start, &self.population(), &self.bounds, evaluations, self.minimization, self.hall_of_fame.as_ref(), observer)?;
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(reason) = tracker.next_iteration(&self.termination, self.max_generations, observer) {
+                break reason;
+            }
+
+            tracker.evaluations += if self.config.parallel {
+                pool.install(|| self.islands.par_iter_mut().map(|ga| ga.next_generation(&pool, objective)).sum::<usize>())
+            }
+            else {
+                self.islands.iter_mut().map(|ga| ga.next_generation(&pool, objective)).sum()
+            };
+
+            if self.config.interval > 0 && (tracker.iteration + 1).is_multiple_of(self.config.interval) {
+                self.migrate();
+            }
+
+            tracker.end_iteration(&self.population(), &self.bounds, observer);
+        };
+
+        let mut result: OptimizationResult = tracker.finish(stop_reason, observer);
+        if let Some(niching) = &self.niching {
+            result.niches = niching.niches(&self.population(), &self.bounds, self.minimization);
+        }
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //chromosome whose only value is its fitness
+    fn chromosome(fitness: f64) -> Chromosome {
+        let mut c: Chromosome = Chromosome::new(vec![fitness]);
+        c.set_fitness(fitness);
+        c
+    }
+
+    #[test]
+    fn ring_migration_sends_the_best_of_each_island_to_the_next() {
+        let metadata: GA_Metadata = GA_Metadata { population_size: 12, seed: Some(1), ..Default::default() };
+        let config: Islands = Islands { count: 3, migrants: 1, topology: MigrationTopology::Ring, ..Default::default() };
+        let mut model: IslandModel = IslandModel::from_metadata(&metadata, config, vec![Bound::new(String::from("x"), 0.0, 100.0)], true);
+
+        //island i has the fitness 10 i to 10 i + 3, sorted from the best
+        for (i, island) in model.islands.iter_mut().enumerate() {
+            island.population = (0..4).map(|k| chromosome((10*i + k) as f64)).collect();
+        }
+        model.migrate();
+
+        let fitness: Vec<Vec<f64>> = model.islands.iter().map(|ga| ga.population.iter().map(|c| c.fitness).collect()).collect();
+        assert_eq!(fitness, vec![vec![0.0, 1.0, 2.0, 20.0], vec![0.0, 10.0, 11.0, 12.0], vec![10.0, 20.0, 21.0, 22.0]]);
+    }
+}
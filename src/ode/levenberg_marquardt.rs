@@ -0,0 +1,362 @@
+use std::time::Instant;
+
+use nalgebra::{DMatrix, DVector};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{is_fitter, random_population, Chromosome};
+use super::ga_json::{Bound, GA_Metadata};
+use super::hall_of_fame::HallOfFameConfig;
+use super::history::History;
+use super::objective::{evaluate_individual, Objective};
+use super::observer::Observer;
+use super::optimizer::{run_seed, OptimizationResult, Optimizer, RunTracker};
+use super::termination::{StopReason, Termination};
+
+//finite differences used to approximate the Jacobian of the residuals
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Jacobian {
+    //one evaluation per parameter
+    #[default]
+    Forward,
+    //two evaluations per parameter, more accurate
+    Central,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LmParameters {
+    pub jacobian: Jacobian,
+    pub damping: f64, //initial damping factor
+    pub tolerance: f64, //relative change of the sum of squares or of the parameters considered as convergence
+    pub max_evaluations: usize, //limit of each local search
+}
+
+impl Default for LmParameters {
+    fn default() -> Self {
+        Self {
+            jacobian: Jacobian::default(),
+            damping: 1e-3,
+            tolerance: 1e-10,
+            max_evaluations: 200,
+        }
+    }
+}
+
+//point of the search with its residuals
+#[derive(Debug, Clone)]
+pub struct LmState {
+    x: Vec<f64>,
+    residuals: DVector<f64>,
+    ssr: f64, //sum of the squared residuals
+    damping: f64,
+}
+
+fn residuals<O: Objective>(objective: &O, worker: &mut O::Worker, x: &[f64]) -> Option<DVector<f64>> {
+    objective.residuals(worker, x)
+        .map(DVector::from_vec)
+        .filter(|r| r.iter().all(|v| v.is_finite()))
+}
+
+//Jacobian of the residuals at `x`, the steps go towards the inside of the bounds, None if some residuals
+//can not be calculated, and the number of evaluations made
+pub fn jacobian<O: Objective>(objective: &O, worker: &mut O::Worker, x: &[f64], r: &DVector<f64>, bounds: &[Bound],
+            scheme: Jacobian) -> (Option<DMatrix<f64>>, usize) {
+
+    let mut j: DMatrix<f64> = DMatrix::zeros(r.len(), x.len());
+    let mut evaluations: usize = 0;
+
+    for (k, bound) in bounds.iter().enumerate() {
+
+        let h: f64 = f64::EPSILON.sqrt()*x[k].abs().max(1e-3*(bound.max - bound.min)).max(f64::EPSILON);
+        let mut forward: Vec<f64> = x.to_vec();
+        let mut backward: Vec<f64> = x.to_vec();
+
+        let column: Option<DVector<f64>> = match scheme {
+            Jacobian::Central if x[k] - h >= bound.min && x[k] + h <= bound.max => {
+                forward[k] += h;
+                backward[k] -= h;
+                evaluations += 1;
+                residuals(objective, worker, &forward).and_then(|f| {
+                    evaluations += 1;
+                    residuals(objective, worker, &backward).map(|b| (f - b)/(2.0*h))
+                })
+            },
+            _ => {
+                let h: f64 = if x[k] + h <= bound.max { h } else { -h };
+                forward[k] += h;
+                evaluations += 1;
+                residuals(objective, worker, &forward).map(|f| (f - r)/h)
+            },
+        };
+
+        match column {
+            Some(c) if c.len() == r.len() => j.set_column(k, &c),
+            _ => return (None, evaluations),
+        }
+    }
+    (Some(j), evaluations)
+}
+
+//covariance of the parameters, the inverse of J'J scaled by the variance of the residuals
+pub fn covariance(j: &DMatrix<f64>, ssr: f64) -> Option<DMatrix<f64>> {
+    let (m, n) = j.shape();
+    let variance: f64 = if m > n { ssr/(m - n) as f64 } else { ssr };
+    (j.transpose()*j).try_inverse().map(|inverse| variance*inverse)
+}
+
+impl LmState {
+
+    pub fn new<O: Objective>(objective: &O, worker: &mut O::Worker, x: &[f64], damping: f64) -> Option<Self> {
+        let residuals: DVector<f64> = residuals(objective, worker, x)?;
+        Some(Self {
+            x: x.to_vec(),
+            ssr: residuals.norm_squared(),
+            residuals,
+            damping,
+        })
+    }
+
+    //one accepted step (or the failure to find one), returns the evaluations and if the search converged
+    pub fn iterate<O: Objective>(&mut self, objective: &O, worker: &mut O::Worker, bounds: &[Bound],
+                parameters: &LmParameters) -> (usize, bool) {
+
+        let (j, mut evaluations) = jacobian(objective, worker, &self.x, &self.residuals, bounds, parameters.jacobian);
+        let j: DMatrix<f64> = match j {
+            Some(j) => j,
+            None => return (evaluations, true),
+        };
+
+        let jtj: DMatrix<f64> = j.transpose()*&j;
+        let gradient: DVector<f64> = j.transpose()*&self.residuals;
+
+        //the damping is increased until the projected step reduces the sum of squares
+        while self.damping < 1e16 {
+
+            let mut a: DMatrix<f64> = jtj.clone();
+            for k in 0..a.nrows() {
+                a[(k, k)] += self.damping*jtj[(k, k)].max(1e-12);
+            }
+
+            let step: DVector<f64> = match a.lu().solve(&(-&gradient)) {
+                Some(s) => s,
+                None => { self.damping *= 10.0; continue; },
+            };
+
+            let candidate: Vec<f64> = self.x.iter()
+                .zip(step.iter())
+                .zip(bounds.iter())
+                .map(|((x, s), b)| b.clamp(x + s))
+                .collect();
+
+            let change: f64 = self.x.iter().zip(candidate.iter()).map(|(x, c)| (x - c).abs()/x.abs().max(1e-12)).fold(0.0, f64::max);
+            if change < parameters.tolerance {
+                return (evaluations, true);
+            }
+
+            evaluations += 1;
+            match residuals(objective, worker, &candidate) {
+                Some(r) if r.norm_squared() < self.ssr => {
+                    let ssr: f64 = r.norm_squared();
+                    let converged: bool = (self.ssr - ssr) <= parameters.tolerance*self.ssr;
+                    self.x = candidate;
+                    self.residuals = r;
+                    self.ssr = ssr;
+                    self.damping = (self.damping/10.0).max(1e-12);
+                    return (evaluations, converged);
+                },
+                _ => self.damping *= 10.0,
+            }
+        }
+        (evaluations, true)
+    }
+
+    //evaluated individual of the current point and the number of evaluations, 0 if the fitness was cached
+    pub fn chromosome<O: Objective>(&self, objective: &O, worker: &mut O::Worker) -> (Chromosome, usize) {
+        let mut c: Chromosome = Chromosome::new(self.x.clone());
+        let evaluations: usize = evaluate_individual(objective, worker, &mut c);
+        (c, evaluations)
+    }
+}
+
+//Levenberg-Marquardt started from an individual, returns the best point found and the number of evaluations
+//the sum of squares is minimized, the start is returned if it has no residuals or if its fitness is better
+pub fn local_search<O: Objective>(objective: &O, worker: &mut O::Worker, start: &Chromosome, bounds: &[Bound],
+            parameters: &LmParameters, minimization: bool) -> (Chromosome, usize) {
+
+    let mut state: LmState = match LmState::new(objective, worker, start.values(), parameters.damping) {
+        Some(s) => s,
+        None => return (start.clone(), 1),
+    };
+    let mut evaluations: usize = 1;
+
+    loop {
+        let (e, converged) = state.iterate(objective, worker, bounds, parameters);
+        evaluations += e;
+        if converged || evaluations >= parameters.max_evaluations {
+            break;
+        }
+    }
+
+    let (refined, count): (Chromosome, usize) = state.chromosome(objective, worker);
+    if is_fitter(start, &refined, minimization) {
+        return (start.clone(), evaluations + count);
+    }
+    (refined, evaluations + count)
+}
+
+//covariance of the parameters at a solution, None if the objective has no residuals or J'J is singular
+pub fn covariance_at<O: Objective>(objective: &O, worker: &mut O::Worker, solution: &Chromosome, bounds: &[Bound],
+            scheme: Jacobian) -> Option<DMatrix<f64>> {
+    let r: DVector<f64> = residuals(objective, worker, solution.values())?;
+    let (j, _) = jacobian(objective, worker, solution.values(), &r, bounds, scheme);
+    covariance(&j?, r.norm_squared())
+}
+
+//Levenberg-Marquardt on its own, restarted from a random point each time it converges
+#[derive(Debug, Clone)]
+pub struct LevenbergMarquardt {
+    parameters: LmParameters,
+    bounds: Vec<Bound>,
+    max_iterations: usize,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    history: History,
+}
+
+impl LevenbergMarquardt {
+
+    pub fn from_metadata(metadata: &GA_Metadata, parameters: LmParameters, bounds: Vec<Bound>) -> Self {
+        let seed: u64 = run_seed(metadata);
+        Self {
+            parameters,
+            bounds,
+            max_iterations: metadata.max_iterations,
+            termination: metadata.termination.clone(),
+            hall_of_fame: metadata.hall_of_fame.clone(),
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            history: History::default(),
+        }
+    }
+
+    //random starting point whose residuals can be calculated
+    fn start<O: Objective>(&mut self, objective: &O, worker: &mut O::Worker) -> (Option<LmState>, usize) {
+        for attempt in 1..=10 {
+            let start: Vec<Chromosome> = random_population(&self.bounds, 1, &mut self.rng);
+            if let Some(state) = LmState::new(objective, worker, start[0].values(), self.parameters.damping) {
+                return (Some(state), attempt);
+            }
+        }
+        (None, 10)
+    }
+}
+
+impl<O: Objective> Optimizer<O> for LevenbergMarquardt {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> {
+
+        let start: Instant = Instant::now();
+
+        let mut worker: O::Worker = objective.worker();
+        let (state, evaluations) = self.start(objective, &mut worker);
+        let mut state: LmState = match state {
+            Some(s) => s,
+            None => { println!("Levenberg-Marquardt needs an objective with residuals"); return Err(()); },
+        };
+
+        let (current, count): (Chromosome, usize) = state.chromosome(objective, &mut worker);
+        let mut tracker: RunTracker = RunTracker::new(start, &[current], &self.bounds, evaluations + count, true, self.hall_of_fame.as_ref(), observer)?;
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(reason) = tracker.next_iteration(&self.termination, self.max_iterations, observer) {
+                break reason;
+            }
+
+            let (evaluations, converged) = state.iterate(objective, &mut worker, &self.bounds, &self.parameters);
+            let (current, count): (Chromosome, usize) = state.chromosome(objective, &mut worker);
+            tracker.evaluations += evaluations + count;
+
+            if converged {
+                let (restarted, evaluations) = self.start(objective, &mut worker);
+                tracker.evaluations += evaluations;
+                if let Some(s) = restarted {
+                    state = s;
+                }
+            }
+
+            tracker.end_iteration(&[current], &self.bounds, observer);
+        };
+
+        tracker.covariance = covariance_at(objective, &mut worker, &tracker.best, &self.bounds, self.parameters.jacobian);
+
+        let result: OptimizationResult = tracker.finish(stop_reason, observer);
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::observer::ConsoleObserver;
+
+    const T: [f64; 5] = [0.0, 1.0, 2.0, 3.0, 4.0];
+    const Y: [f64; 5] = [1.1, 2.9, 5.2, 6.8, 9.1];
+
+    //straight line a + b t fitted to (T, Y)
+    struct Line;
+
+    impl Objective for Line {
+        type Worker = ();
+
+        fn worker(&self) {}
+
+        fn evaluate(&self, worker: &mut (), values: &[f64]) -> f64 {
+            self.residuals(worker, values).map_or(f64::INFINITY, |r| r.iter().map(|v| v*v).sum())
+        }
+
+        fn residuals(&self, _worker: &mut (), values: &[f64]) -> Option<Vec<f64>> {
+            Some(T.iter().zip(Y.iter()).map(|(t, y)| values[0] + values[1]*t - y).collect())
+        }
+    }
+
+    #[test]
+    fn fits_a_line_with_the_least_squares_covariance() {
+        let bounds: Vec<Bound> = vec![Bound::new(String::from("a"), -10.0, 10.0), Bound::new(String::from("b"), -10.0, 10.0)];
+        let metadata: GA_Metadata = GA_Metadata { max_iterations: 20, seed: Some(1), ..Default::default() };
+
+        let result: OptimizationResult = LevenbergMarquardt::from_metadata(&metadata, LmParameters::default(), bounds)
+            .optimize(&Line, &mut ConsoleObserver)
+            .unwrap();
+
+        //mean of t 2, sum of (t - 2)^2 10, b = 19.9/10, a = 5.02 - 2 b and the sum of squares is 0.107
+        let values: &[f64] = result.best.values();
+        assert!((values[0] - 1.04).abs() < 1e-6 && (values[1] - 1.99).abs() < 1e-6);
+        assert!((result.best.fitness - 0.107).abs() < 1e-9);
+
+        //s^2 = 0.107/(5 - 2), var(a) = s^2 (1/5 + 2^2/10), var(b) = s^2/10, cov(a, b) = -2 s^2/10
+        let s2: f64 = 0.107/3.0;
+        let expected: DMatrix<f64> = DMatrix::from_row_slice(2, 2, &[0.6*s2, -0.2*s2, -0.2*s2, 0.1*s2]);
+        let covariance: DMatrix<f64> = result.covariance.unwrap();
+        assert!((covariance - expected).abs().max() < 1e-6);
+    }
+}
@@ -0,0 +1,91 @@
+use nalgebra::DMatrix;
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{sort_population, Chromosome};
+use super::ga_json::Bound;
+use super::history::Refinement;
+use super::levenberg_marquardt::{self, LmParameters};
+use super::nelder_mead::{self, NelderMeadParameters};
+use super::objective::Objective;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum LocalMethod {
+    NelderMead(NelderMeadParameters),
+    //needs an objective with residuals, such as the fitting of the experimental data
+    LevenbergMarquardt(LmParameters),
+}
+
+impl Default for LocalMethod {
+    fn default() -> Self {
+        LocalMethod::NelderMead(NelderMeadParameters::default())
+    }
+}
+
+//polishing of the best individuals of the GA (memetic algorithm)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LocalSearch {
+    pub interval: Option<usize>, //generations between refinements, None refines only at the end of the run
+    pub top: usize, //number of individuals refined
+    pub method: LocalMethod,
+}
+
+impl Default for LocalSearch {
+    fn default() -> Self {
+        Self {
+            interval: None,
+            top: 1,
+            method: LocalMethod::default(),
+        }
+    }
+}
+
+impl LocalSearch {
+
+    //replaces the `top` first individuals of a sorted population by their refined versions
+    pub fn refine<O: Objective>(&self, pool: &ThreadPool, population: &mut [Chromosome], generation: usize, objective: &O,
+                bounds: &[Bound], minimization: bool) -> Refinement {
+
+        let count: usize = self.top.min(population.len());
+        let individuals: &mut [Chromosome] = &mut population[..count];
+        let before: f64 = individuals.first().map_or(f64::NAN, |c| c.fitness);
+
+        let evaluations: usize = pool.install(|| {
+            individuals
+                .par_iter_mut()
+                .map_init(|| objective.worker(), | worker, c | {
+                    let (refined, evaluations) = match &self.method {
+                        LocalMethod::NelderMead(parameters) =>
+                            nelder_mead::local_search(objective, worker, c, bounds, parameters, minimization),
+                        LocalMethod::LevenbergMarquardt(parameters) =>
+                            levenberg_marquardt::local_search(objective, worker, c, bounds, parameters, minimization),
+                    };
+                    *c = refined;
+                    evaluations
+                })
+                .sum()
+        });
+
+        sort_population(population, minimization);
+
+        Refinement {
+            generation,
+            individuals: count,
+            before,
+            after: population.first().map_or(f64::NAN, |c| c.fitness),
+            evaluations,
+        }
+    }
+
+    //covariance of the parameters at a refined solution, only Levenberg-Marquardt provides one
+    pub fn covariance<O: Objective>(&self, objective: &O, solution: &Chromosome, bounds: &[Bound]) -> Option<DMatrix<f64>> {
+        match &self.method {
+            LocalMethod::NelderMead(_) => None,
+            LocalMethod::LevenbergMarquardt(parameters) =>
+                levenberg_marquardt::covariance_at(objective, &mut objective.worker(), solution, bounds, parameters.jacobian),
+        }
+    }
+}
@@ -1,18 +1,44 @@
 mod ga;
+mod cache;
+mod checkpoint;
+mod constraints;
+mod cmaes;
 mod csvdata;
+mod crossover;
+mod de;
+mod diversity;
+mod encoding;
+mod hall_of_fame;
+mod history;
+mod initialization;
+mod islands;
+mod levenberg_marquardt;
+mod local_search;
+mod mutation;
+mod nelder_mead;
+mod niching;
+mod nsga2;
+mod objective;
+pub mod observer;
+mod optimizer;
+mod pso;
+mod replacement;
+mod restart;
+mod selection;
+mod termination;
 pub mod ga_json;
 pub mod model;
 
 use std::{collections::BTreeMap, fs::File, io::{BufReader, Read}};
-use ode_solvers::DVector;
-use crate::ode::model::solve;
 
-use self::{csvdata::CSVData, ga::GA, ga_json::{load_json, Bound, ConfigData}, model::{create_ode_system, OdeSystem, State}};
+use self::{checkpoint::{config_hash, load_checkpoint, Checkpoint}, csvdata::CSVData, ga_json::{load_json, Bound, ConfigData}, model::{create_ode_system, OdeSystem}, 
+    objective::DataFitting, observer::{ConsoleObserver, Observer}, optimizer::{create_optimizer, Algorithm, OptimizationResult, Optimizer},
+    encoding::{decode_result, DecodedObserver, Encoded}, nsga2::save_pareto_front, constraints::{Constrained, ConstraintSet},
+    hall_of_fame::save_hall_of_fame, cache::Cached};
 /* Objective: to find the parameter values that better adjust the set of experimental data. */
 
 #[derive(Debug,Clone)]
 pub struct ParameterEstimation {
-    ga: GA,
     best_solution: Vec<f64>,
     data_file: String,
     config_data: ConfigData, 
@@ -24,7 +50,6 @@ impl ParameterEstimation {
 
     pub fn new(file_name: String) -> Self {
         Self {
-            ga: GA::default(),
             best_solution: vec![],
             data_file: file_name,
             config_data: ConfigData::default(),
@@ -49,6 +74,31 @@ impl ParameterEstimation {
     }
                 
     pub fn estimate_parameters(&mut self, ode_system: &mut OdeSystem){
+        self.estimate_parameters_with_observer(ode_system, &mut ConsoleObserver);
+    }
+
+    //the observer is notified of the progress of the optimization and can cancel it
+    pub fn estimate_parameters_with_observer(&mut self, ode_system: &mut OdeSystem, observer: &mut dyn Observer){
+        self.run(ode_system, None, observer);
+    }
+
+    //continues an estimation from a checkpoint saved with the same configuration
+    pub fn resume_parameter_estimation(&mut self, ode_system: &mut OdeSystem, checkpoint_path: &str){
+
+        let checkpoint: Checkpoint = match load_checkpoint(checkpoint_path) {
+            Ok(c) => c,
+            Err(e) => { println!("An error ocurred on reading the checkpoint file: {:?}", e); return; },
+        };
+
+        if checkpoint.config_hash != config_hash(&self.config_data) {
+            println!("The checkpoint {} was created with a different configuration", checkpoint_path);
+            return;
+        }
+
+        self.run(ode_system, Some(checkpoint), &mut ConsoleObserver);
+    }
+
+    fn run(&mut self, ode_system: &mut OdeSystem, checkpoint: Option<Checkpoint>, observer: &mut dyn Observer){
         
         match CSVData::load_data(File::open(self.data_file.clone()).unwrap()){
             Ok(csv_data) => {
@@ -58,87 +108,109 @@ impl ParameterEstimation {
                     bounds.insert(bound.name.clone(), bound.clone());
                 }                
 
-                self.ga = GA::new(
-                    self.config_data.metadata.max_iterations, 
-                    self.config_data.metadata.mutation_rate, 
-                    self.config_data.metadata.crossover_rate, 
-                    self.config_data.bounds.clone(),
-                    true
-                );
-
-                self.ga.generate_random_population(
-                    self.config_data.metadata.population_size, 
-                    self.config_data.bounds.len()
-                );                
-
-                let mut indexes: Vec<usize> = vec![];
-                for label in csv_data.labels.iter() {
-                    let mut pop_index: usize = 0;
-                    for key in ode_system.equations.keys() {
-                        if label.trim() == key.trim() {
-                            indexes.push(pop_index);
-                        }
-                        pop_index += 1;
+                let mut optimizer: Box<dyn Optimizer<Cached<Encoded<Constrained<DataFitting>>>>> = match create_optimizer(&self.config_data, true) {
+                    Ok(o) => o,
+                    Err(_) => return,
+                };
+
+                if let Some(c) = checkpoint {
+                    if !optimizer.resume(c) {
+                        println!("The algorithm {:?} can not be resumed from a checkpoint", self.config_data.metadata.algorithm);
+                        return;
                     }
                 }
 
-                let y: State = State::from_vec(ode_system.equations.keys()
-                        .map(|k| ode_system.get_argument_value(k.to_string())).collect());
-
-                ode_system.update_context_with_state(&y);
+                //the optimizer works with encoded values, the objective and the observer see the natural ones
+                let mut fitting: DataFitting = DataFitting::new(ode_system, csv_data);
+                if let Algorithm::Nsga2(parameters) = &self.config_data.metadata.algorithm {
+                    fitting = match fitting.with_groups(&parameters.groups) {
+                        Ok(f) => f,
+                        Err(_) => return,
+                    };
+                }
+                let objective_names: Vec<String> = fitting.objective_names();
+
+                let config_bounds: Vec<Bound> = self.config_data.bounds.clone();
+                let constraints: ConstraintSet = match ConstraintSet::new(&self.config_data.metadata.constraints.clone().unwrap_or_default(), 
+                        &self.config_data.arguments, &config_bounds, true) {
+                    Ok(c) => c,
+                    Err(_) => return,
+                };
+                let objective: Cached<Encoded<Constrained<DataFitting>>> = Cached::new(
+                    Encoded::new(Constrained::new(fitting, constraints.clone()), config_bounds.clone()), 
+                    optimizer.bounds().to_vec(), self.config_data.metadata.cache.clone());
+                let mut observer: DecodedObserver = DecodedObserver::new(observer, &config_bounds);
             
-                match self.ga.optimize( |values: &Vec<f64>| {  
-               
-
-                    let mut errors: Vec<f64> = vec![0.0; csv_data.labels.len()];                 
-                    
-                    ode_system.update_context(values);                    
-
-                    //println!("context: {:#?}", ode_system.context);
-                    
-                    let ode_result: Vec<DVector<f64>> = solve(ode_system, &y);
-                    if ode_result.len() == 0 {
-                        return 1000.0;
-                    }
-
-                    let mut index: usize = 0;
-                    let mut ode_index: usize = 0;
-                    let mut t: f64 = self.config_data.metadata.start_time; 
-                    let dt: f64 = self.config_data.metadata.delta_time;
-                    let t_end = self.config_data.metadata.end_time;
-
-                    while t <= t_end {
-                                                
-                        if index == csv_data.time.len() {
-                            break;
+                match optimizer.optimize(&objective, &mut observer) {
+                    Ok(result) => { 
+                        let result: OptimizationResult = constraints.repair_result(&decode_result(&config_bounds, &result));
+                        println!("The best individual is {:?} (seed = {})", result.best, optimizer.seed()); 
+                        println!("Stop reason: {} ({} generations, {} evaluations)", 
+                            result.stop_reason, result.generations, result.evaluations); 
+                        self.best_solution = result.best.get_values(); 
+
+                        for (bound, value) in optimizer.bounds().iter().zip(self.best_solution.iter()) {
+                            println!("{} = {}", bound.name, value);
                         }
 
-                        if  (t - csv_data.time[index]).abs() < 10.0_f64.powf(-6.0) {
-
-                            for i in 0..csv_data.labels.len() {                                
+                        for (constraint, violation) in constraints.report(&result.best) {
+                            println!("{}: violation = {}", constraint, violation);
+                        }
 
-                                let data: f64 = csv_data.lines[i][index];
+                        if let Some(covariance) = &result.covariance {
+                            println!("Covariance of the parameters:");
+                            for row in covariance.row_iter() {
+                                println!("{}", row.iter().map(|v| format!("{:e}", v)).collect::<Vec<String>>().join(" "));
+                            }
+                            for (i, bound) in optimizer.bounds().iter().enumerate() {
+                                println!("{} standard deviation = {}", bound.name, covariance[(i, i)].sqrt());
+                            }
+                        }
 
-                                let dif = ode_result[ode_index][indexes[i]] - data;
-                                errors[i] += dif*dif;
+                        if !result.hall_of_fame.is_empty() {
+                            println!("Hall of fame:");
+                            for (rank, member) in result.hall_of_fame.iter().enumerate() {
+                                println!("{} {:?} fitness = {}", rank + 1, member.values(), member.fitness);
+                            }
+                            if let Some(path) = self.config_data.metadata.hall_of_fame.as_ref().and_then(|h| h.path.as_ref()) {
+                                if let Err(e) = save_hall_of_fame(path, &result.hall_of_fame, optimizer.bounds()) {
+                                    println!("An error ocurred on saving the hall of fame: {:?}", e);
+                                }
+                            }
+                        }
 
+                        if !result.niches.is_empty() {
+                            println!("{} niches found:", result.niches.len());
+                            for niche in result.niches.iter() {
+                                println!("{:?} fitness = {} ({} individuals)", niche.best.values(), niche.best.fitness, niche.size);
                             }
+                        }
 
-                            index += 1;
+                        if !result.pareto_front.is_empty() {
+                            println!("Pareto front with {} solutions ({})", result.pareto_front.len(), objective_names.join(", "));
+                            for solution in result.pareto_front.iter() {
+                                let violation: f64 = constraints.report(&solution.chromosome).iter().fold(0.0, |s, (_, v)| s + v);
+                                println!("{:?} objectives = {:?} violation = {}", solution.chromosome.values(), solution.objectives, violation);
+                            }
+                            if let Algorithm::Nsga2(parameters) = &self.config_data.metadata.algorithm {
+                                if let Some(path) = &parameters.front_file {
+                                    if let Err(e) = save_pareto_front(path, &result.pareto_front, optimizer.bounds(), &objective_names) {
+                                        println!("An error ocurred on saving the Pareto front: {:?}", e);
+                                    }
+                                }
+                            }
                         }
 
-                        t += dt;
-                        ode_index += 1;
-                    }
+                        if let Some(stats) = objective.stats() {
+                            println!("Evaluation cache: {} hits, {} misses ({:.1}% hit rate)", stats.hits, stats.misses, 100.0*stats.hit_rate());
+                        }
 
-                    let sum: f64 = errors.iter().sum();
-                    if sum.is_nan(){
-                        return 1000.0;
-                    }
-                    
-                    return sum.sqrt();
-                } ){                    
-                    Ok(c) => { println!("The best individual is {:?}", c); self.best_solution = c.get_values(); },
+                        if let Some(path) = &self.config_data.metadata.history_file {
+                            if let Err(e) = optimizer.history().save(path) {
+                                println!("An error ocurred on saving the history: {:?}", e);
+                            }
+                        }
+                    },
                     Err(e) => println!("An error ocurred during the optimization: {:?}", e),
                 }                    
             },
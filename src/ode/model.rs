@@ -13,6 +13,7 @@ pub struct OdeSystem {
     pub config_data: ConfigData,
     pub equations: BTreeMap<String,(Expression<f64>, f64)>,
     pub context: Context<f64>,
+    pub source: String, //text of the equations, used to rebuild the system in other threads
 }
 
 impl OdeSystem {
@@ -21,6 +22,7 @@ impl OdeSystem {
             config_data: cfg,
             equations: BTreeMap::new(),
             context: Context::new(),            
+            source: String::new(),
         }
     }
 
@@ -34,7 +36,7 @@ impl OdeSystem {
         return 0.0
     }
 
-    pub fn update_context(&mut self, values: &Vec<f64>) {
+    pub fn update_context(&mut self, values: &[f64]) {
 
         self.config_data.bounds
                 .iter()
@@ -103,6 +105,7 @@ pub fn solve(ode_system: &mut OdeSystem, y: &State) -> Vec<State> {
 pub fn create_ode_system(input: String, config_data: &ConfigData) -> OdeSystem {
         
     let mut ode_system = OdeSystem::new(config_data.clone());       
+    ode_system.source = input.clone();
     
     let lines = input.split("\n").collect::<Vec<_>>(); 
 
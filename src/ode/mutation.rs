@@ -0,0 +1,130 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::ga_json::Bound;
+
+//operator used to perturb the genes of an individual
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Mutation {
+    //a random gene moves by mutation_percentage * value (at most 1.0), applied with probability mutation_rate
+    #[default]
+    Percentage,
+    //each gene moves, with probability mutation_rate, by a normal step with standard deviation `sigma` times the bound width
+    Gaussian { sigma: f64 },
+    //polynomial mutation of NSGA-II, each gene with probability mutation_rate
+    Polynomial { eta: f64 },
+    //every gene carries its own step size, which is mutated before the gene (log-normal rule)
+    //`initial_step` is the first step size as a fraction of the bound width
+    SelfAdaptive { initial_step: f64 },
+}
+
+impl Mutation {
+
+    pub fn mutate<R: Rng + ?Sized>(&self, values: &mut [f64], steps: &mut Vec<f64>, mutation_percentage: f64,
+                                    mutation_rate: f64, bounds: &[Bound], rng: &mut R) {
+
+        if values.is_empty() {
+            return;
+        }
+
+        match self {
+            Mutation::Percentage => percentage(values, mutation_percentage, mutation_rate, bounds, rng),
+            Mutation::Gaussian { sigma } => gaussian(values, *sigma, mutation_rate, bounds, rng),
+            Mutation::Polynomial { eta } => polynomial(values, *eta, mutation_rate, bounds, rng),
+            Mutation::SelfAdaptive { initial_step } => {
+                if steps.len() != values.len() {
+                    *steps = bounds.iter().map(|b| initial_step*(b.max - b.min)).collect();
+                }
+                self_adaptive(values, steps, mutation_rate, bounds, rng);
+            },
+        }
+    }
+}
+
+//normal sample with mean 0 and standard deviation 1 (Box-Muller)
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen_range(0.0..1.0);
+    (-2.0*u1.ln()).sqrt() * (2.0*std::f64::consts::PI*u2).cos()
+}
+
+fn percentage<R: Rng + ?Sized>(values: &mut [f64], mutation_percentage: f64, mutation_rate: f64, bounds: &[Bound], rng: &mut R) {
+
+    let c_index: usize = rng.gen_range(0..values.len());
+    let mut p: f64 = rng.gen_range(0.0..=1.0);
+
+    if p < mutation_rate {
+
+        p = rng.gen_range(0.0..=1.0);
+
+        let mut temp_value = mutation_percentage*values[c_index];
+        if temp_value > 1. {
+            temp_value = 1.;
+        }
+
+        if p < 0.5 {
+            values[c_index] += temp_value;
+        }
+        else {
+            values[c_index] -= temp_value;
+        }
+        values[c_index] = bounds[c_index].clamp(values[c_index]);
+    }
+}
+
+fn gaussian<R: Rng + ?Sized>(values: &mut [f64], sigma: f64, mutation_rate: f64, bounds: &[Bound], rng: &mut R) {
+
+    for (value, bound) in values.iter_mut().zip(bounds) {
+        if rng.gen_range(0.0..1.0) < mutation_rate {
+            *value = bound.clamp(*value + sigma*(bound.max - bound.min)*standard_normal(rng));
+        }
+    }
+}
+
+fn polynomial<R: Rng + ?Sized>(values: &mut [f64], eta: f64, mutation_rate: f64, bounds: &[Bound], rng: &mut R) {
+
+    for (value, bound) in values.iter_mut().zip(bounds) {
+
+        let width: f64 = bound.max - bound.min;
+        if width <= 0.0 || rng.gen_range(0.0..1.0) >= mutation_rate {
+            continue;
+        }
+
+        let delta_1: f64 = (*value - bound.min)/width;
+        let delta_2: f64 = (bound.max - *value)/width;
+        let u: f64 = rng.gen_range(0.0..1.0);
+        let power: f64 = 1.0/(eta + 1.0);
+
+        let delta_q: f64 = if u <= 0.5 {
+            let v: f64 = 2.0*u + (1.0 - 2.0*u)*(1.0 - delta_1).powf(eta + 1.0);
+            v.powf(power) - 1.0
+        }
+        else {
+            let v: f64 = 2.0*(1.0 - u) + 2.0*(u - 0.5)*(1.0 - delta_2).powf(eta + 1.0);
+            1.0 - v.powf(power)
+        };
+
+        *value = bound.clamp(*value + delta_q*width);
+    }
+}
+
+fn self_adaptive<R: Rng + ?Sized>(values: &mut [f64], steps: &mut [f64], mutation_rate: f64, bounds: &[Bound], rng: &mut R) {
+
+    if rng.gen_range(0.0..1.0) >= mutation_rate {
+        return;
+    }
+
+    let n: f64 = values.len() as f64;
+    let tau: f64 = 1.0/(2.0*n.sqrt()).sqrt();
+    let tau_global: f64 = 1.0/(2.0*n).sqrt();
+    let global: f64 = tau_global*standard_normal(rng);
+
+    for ((value, step), bound) in values.iter_mut().zip(steps.iter_mut()).zip(bounds) {
+        let width: f64 = bound.max - bound.min;
+        let adapted: f64 = *step*(global + tau*standard_normal(rng)).exp();
+        //f64::clamp panics with a NaN or inverted range
+        *step = if width.is_finite() && width >= 0.0 { adapted.clamp(1.0e-12*width, width) } else { adapted };
+        *value = bound.clamp(*value + *step*standard_normal(rng));
+    }
+}
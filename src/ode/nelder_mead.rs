@@ -0,0 +1,280 @@
+use std::time::Instant;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{is_fitter, random_population, sort_population, Chromosome};
+use super::ga_json::{Bound, GA_Metadata};
+use super::hall_of_fame::HallOfFameConfig;
+use super::history::History;
+use super::objective::{evaluate_individual, Objective};
+use super::observer::Observer;
+use super::optimizer::{run_seed, OptimizationResult, Optimizer, RunTracker};
+use super::termination::{StopReason, Termination};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NelderMeadParameters {
+    pub step: f64, //size of the initial simplex as a fraction of the width of the bounds
+    pub tolerance: f64, //the simplex has converged when the fitness of its vertices differ less than this
+    pub max_evaluations: usize, //limit of each local search
+}
+
+impl Default for NelderMeadParameters {
+    fn default() -> Self {
+        Self {
+            step: 0.1,
+            tolerance: 1e-10,
+            max_evaluations: 200,
+        }
+    }
+}
+
+//simplex sorted from the best to the worst vertex, every vertex is kept inside the bounds
+#[derive(Debug, Clone)]
+pub struct Simplex {
+    vertices: Vec<Chromosome>,
+    minimization: bool,
+}
+
+impl Simplex {
+
+    //`start` must already be evaluated, the other vertices are displaced along each axis
+    pub fn new<O: Objective>(objective: &O, worker: &mut O::Worker, start: &Chromosome, bounds: &[Bound], step: f64,
+                minimization: bool) -> (Self, usize) {
+
+        let mut vertices: Vec<Chromosome> = vec![start.clone()];
+        let mut evaluations: usize = 0;
+
+        for (j, bound) in bounds.iter().enumerate() {
+            let mut values: Vec<f64> = start.get_values();
+            let delta: f64 = step*(bound.max - bound.min);
+            values[j] = if values[j] + delta <= bound.max { values[j] + delta } else { values[j] - delta };
+            let (vertex, count) = evaluate(objective, worker, bound_values(values, bounds));
+            vertices.push(vertex);
+            evaluations += count;
+        }
+
+        let mut simplex: Simplex = Simplex { vertices, minimization };
+        simplex.sort();
+        (simplex, evaluations)
+    }
+
+    pub fn best(&self) -> &Chromosome {
+        &self.vertices[0]
+    }
+
+    pub fn vertices(&self) -> &[Chromosome] {
+        &self.vertices
+    }
+
+    //difference between the fitness of the worst and the best vertices
+    pub fn spread(&self) -> f64 {
+        (self.vertices[self.vertices.len() - 1].fitness - self.vertices[0].fitness).abs()
+    }
+
+    fn sort(&mut self) {
+        sort_population(&mut self.vertices, self.minimization);
+    }
+
+    //point c + t*(x - c), kept inside the bounds
+    fn point(centroid: &[f64], x: &[f64], t: f64, bounds: &[Bound]) -> Vec<f64> {
+        bound_values(centroid.iter().zip(x.iter()).map(|(c, x)| c + t*(x - c)).collect(), bounds)
+    }
+
+    //one reflection, expansion, contraction or shrink step, returns the number of evaluations
+    pub fn iterate<O: Objective>(&mut self, objective: &O, worker: &mut O::Worker, bounds: &[Bound]) -> usize {
+
+        let n: usize = self.vertices.len() - 1;
+        let min: bool = self.minimization;
+
+        let centroid: Vec<f64> = (0..bounds.len())
+            .map(|j| self.vertices[..n].iter().map(|v| v.values()[j]).sum::<f64>()/n as f64)
+            .collect();
+        let worst: Chromosome = self.vertices[n].clone();
+
+        let (reflected, mut evaluations): (Chromosome, usize) = evaluate(objective, worker, Simplex::point(&centroid, worst.values(), -1.0, bounds));
+
+        if is_fitter(&reflected, &self.vertices[0], min) {
+            let (expanded, count): (Chromosome, usize) = evaluate(objective, worker, Simplex::point(&centroid, worst.values(), -2.0, bounds));
+            evaluations += count;
+            self.vertices[n] = if is_fitter(&expanded, &reflected, min) { expanded } else { reflected };
+        }
+        else if is_fitter(&reflected, &self.vertices[n - 1], min) {
+            self.vertices[n] = reflected;
+        }
+        else {
+            //outside contraction if the reflected point improves the worst vertex, inside otherwise
+            let outside: bool = is_fitter(&reflected, &worst, min);
+            let t: f64 = if outside { -0.5 } else { 0.5 };
+            let (contracted, count): (Chromosome, usize) = evaluate(objective, worker, Simplex::point(&centroid, worst.values(), t, bounds));
+            evaluations += count;
+
+            let reference: &Chromosome = if outside { &reflected } else { &worst };
+            if !is_fitter(reference, &contracted, min) {
+                self.vertices[n] = contracted;
+            }
+            else {
+                let best: Vec<f64> = self.vertices[0].get_values();
+                for vertex in self.vertices.iter_mut().skip(1) {
+                    let (shrunk, count): (Chromosome, usize) = evaluate(objective, worker, Simplex::point(&best, vertex.values(), 0.5, bounds));
+                    *vertex = shrunk;
+                    evaluations += count;
+                }
+            }
+        }
+
+        self.sort();
+        evaluations
+    }
+}
+
+fn bound_values(values: Vec<f64>, bounds: &[Bound]) -> Vec<f64> {
+    values.into_iter().zip(bounds.iter()).map(|(v, b)| b.clamp(v)).collect()
+}
+
+//evaluated individual and the number of evaluations, 0 if the fitness was cached
+fn evaluate<O: Objective>(objective: &O, worker: &mut O::Worker, values: Vec<f64>) -> (Chromosome, usize) {
+    let mut c: Chromosome = Chromosome::new(values);
+    let evaluations: usize = evaluate_individual(objective, worker, &mut c);
+    (c, evaluations)
+}
+
+//Nelder-Mead started from an evaluated individual, returns the best vertex and the number of evaluations
+pub fn local_search<O: Objective>(objective: &O, worker: &mut O::Worker, start: &Chromosome, bounds: &[Bound],
+            parameters: &NelderMeadParameters, minimization: bool) -> (Chromosome, usize) {
+
+    if bounds.is_empty() {
+        return (start.clone(), 0);
+    }
+
+    let (mut simplex, mut evaluations) = Simplex::new(objective, worker, start, bounds, parameters.step, minimization);
+
+    //the steps are limited too, the cached points are not counted as evaluations
+    let mut steps: usize = 0;
+    while evaluations < parameters.max_evaluations && steps < parameters.max_evaluations && simplex.spread() > parameters.tolerance {
+        evaluations += simplex.iterate(objective, worker, bounds);
+        steps += 1;
+    }
+
+    //a worse vertex can only come from the bounds, the start is kept in that case
+    if is_fitter(start, simplex.best(), minimization) {
+        return (start.clone(), evaluations);
+    }
+    (simplex.best().clone(), evaluations)
+}
+
+//Nelder-Mead on its own, restarted from a random point each time the simplex converges
+#[derive(Debug, Clone)]
+pub struct NelderMead {
+    parameters: NelderMeadParameters,
+    bounds: Vec<Bound>,
+    minimization: bool,
+    max_iterations: usize,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    history: History,
+}
+
+impl NelderMead {
+
+    pub fn from_metadata(metadata: &GA_Metadata, parameters: NelderMeadParameters, bounds: Vec<Bound>, is_min: bool) -> Self {
+        let seed: u64 = run_seed(metadata);
+        Self {
+            parameters,
+            bounds,
+            minimization: is_min,
+            max_iterations: metadata.max_iterations,
+            termination: metadata.termination.clone(),
+            hall_of_fame: metadata.hall_of_fame.clone(),
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            history: History::default(),
+        }
+    }
+
+    fn start<O: Objective>(&mut self, objective: &O, worker: &mut O::Worker) -> (Simplex, usize) {
+        let mut start: Vec<Chromosome> = random_population(&self.bounds, 1, &mut self.rng);
+        let count: usize = evaluate_individual(objective, worker, &mut start[0]);
+        let (simplex, evaluations) = Simplex::new(objective, worker, &start[0], &self.bounds, self.parameters.step, self.minimization);
+        (simplex, evaluations + count)
+    }
+}
+
+impl<O: Objective> Optimizer<O> for NelderMead {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> {
+
+        let start: Instant = Instant::now();
+
+        if self.bounds.is_empty() {
+            println!("Nelder-Mead needs at least one bound");
+            return Err(());
+        }
+
+        //the simplex is sequential, a single worker is enough
+        let mut worker: O::Worker = objective.worker();
+        let (mut simplex, evaluations) = self.start(objective, &mut worker);
+
+        let mut tracker: RunTracker = RunTracker::new(start, simplex.vertices(), &self.bounds, evaluations, self.minimization, self.hall_of_fame.as_ref(), observer)?;
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(reason) = tracker.next_iteration(&self.termination, self.max_iterations, observer) {
+                break reason;
+            }
+
+            if simplex.spread() <= self.parameters.tolerance {
+                let (restarted, evaluations) = self.start(objective, &mut worker);
+                simplex = restarted;
+                tracker.evaluations += evaluations;
+            }
+            else {
+                tracker.evaluations += simplex.iterate(objective, &mut worker, &self.bounds);
+            }
+
+            tracker.end_iteration(simplex.vertices(), &self.bounds, observer);
+        };
+
+        let result: OptimizationResult = tracker.finish(stop_reason, observer);
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::observer::ConsoleObserver;
+
+    type Rosenbrock = (fn(), fn(&mut (), &[f64]) -> f64);
+
+    #[test]
+    fn converges_to_the_minimum_of_the_rosenbrock_function() {
+        let bounds: Vec<Bound> = vec![Bound::new(String::from("x"), -5.0, 5.0), Bound::new(String::from("y"), -5.0, 5.0)];
+        let objective: Rosenbrock = (|| (), |_, values| (1.0 - values[0]).powi(2) + 100.0*(values[1] - values[0]*values[0]).powi(2));
+        let metadata: GA_Metadata = GA_Metadata { max_iterations: 500, seed: Some(1), ..Default::default() };
+
+        let result: OptimizationResult = NelderMead::from_metadata(&metadata, NelderMeadParameters::default(), bounds, true)
+            .optimize(&objective, &mut ConsoleObserver)
+            .unwrap();
+        assert!(result.best.fitness < 1e-8);
+        assert!((result.best.values()[0] - 1.0).abs() < 1e-3 && (result.best.values()[1] - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,234 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use super::diversity::{distance, normalize};
+use super::ga::{is_better, is_fitter, sort_population, Chromosome};
+use super::ga_json::Bound;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NichingMethod {
+    //the fitness is degraded by the number of individuals in the niche (Goldberg and Richardson)
+    #[default]
+    Sharing,
+    //only the `capacity` best individuals of each niche keep their fitness (Petrowski)
+    Clearing,
+    //deterministic crowding, the parents are paired at random instead of selected and each child competes
+    //with its closest parent (Mahfoud)
+    Crowding,
+}
+
+//keeps several optima of the fitness in the population of the GA
+//with sharing and clearing only the selection of the parents uses the modified fitness, the elitism and the
+//replacement use the fitness of the individuals, which must not be negative
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Niching {
+    pub method: NichingMethod,
+    pub radius: f64, //normalized distance (0 to 1, as the diversity) of the individuals of a niche
+    pub alpha: f64, //shape of the sharing function
+    pub capacity: usize, //winners of each niche in the clearing
+}
+
+impl Default for Niching {
+    fn default() -> Self {
+        Self {
+            method: NichingMethod::default(),
+            radius: 0.1,
+            alpha: 1.0,
+            capacity: 1,
+        }
+    }
+}
+
+//best individual of a niche of the final population and the number of individuals in it
+#[derive(Debug, Clone)]
+pub struct Niche {
+    pub best: Chromosome,
+    pub size: usize,
+}
+
+fn normalized_distance(a: &Chromosome, b: &Chromosome, bounds: &[Bound]) -> f64 {
+    distance(&normalize(a.values(), bounds), &normalize(b.values(), bounds))/(bounds.len() as f64).sqrt().max(1.0)
+}
+
+impl Niching {
+
+    //copy of the population with the shared or cleared fitness
+    pub fn adjusted(&self, population: &[Chromosome], bounds: &[Bound], minimization: bool) -> Vec<Chromosome> {
+
+        let fitness: Vec<f64> = match self.method {
+            NichingMethod::Sharing => self.shared(population, bounds, minimization),
+            NichingMethod::Clearing => self.cleared(population, bounds, minimization),
+            NichingMethod::Crowding => population.iter().map(|c| c.fitness).collect(),
+        };
+
+        population
+            .iter()
+            .zip(fitness)
+            .map(|(c, f)| { let mut c: Chromosome = c.clone(); c.fitness = f; c })
+            .collect()
+    }
+
+    //fitness divided by the niche count, a minimized fitness f is shared as 1/(1 + f) so that the individuals
+    //with a fitness close to 0 are penalized as well
+    fn shared(&self, population: &[Chromosome], bounds: &[Bound], minimization: bool) -> Vec<f64> {
+
+        population
+            .iter()
+            .map(|c| {
+                let count: f64 = population
+                    .iter()
+                    .map(|other| normalized_distance(c, other, bounds))
+                    .filter(|d| *d < self.radius)
+                    .map(|d| 1.0 - (d/self.radius).powf(self.alpha))
+                    .sum::<f64>()
+                    .max(1.0);
+                if minimization { (1.0 + c.fitness)*count - 1.0 } else { c.fitness/count }
+            })
+            .collect()
+    }
+
+    //the individuals that are not among the best of their niche get the worst fitness of the population
+    fn cleared(&self, population: &[Chromosome], bounds: &[Bound], minimization: bool) -> Vec<f64> {
+
+        let worst: f64 = population
+            .iter()
+            .map(|c| c.fitness)
+            .filter(|f| f.is_finite())
+            .fold(f64::NAN, |w, f| if w.is_nan() || is_better(w, f, minimization) { f } else { w });
+
+        let mut order: Vec<usize> = (0..population.len()).collect();
+        order.sort_by(|a, b| compare(population[*a].fitness, population[*b].fitness, minimization));
+
+        let mut fitness: Vec<f64> = population.iter().map(|c| c.fitness).collect();
+        let mut cleared: Vec<bool> = vec![false; population.len()];
+
+        for (k, i) in order.iter().enumerate() {
+            if cleared[*i] {
+                continue;
+            }
+            let mut winners: usize = 1;
+            for j in order.iter().skip(k + 1) {
+                if cleared[*j] || normalized_distance(&population[*i], &population[*j], bounds) >= self.radius {
+                    continue;
+                }
+                if winners < self.capacity {
+                    winners += 1;
+                }
+                else {
+                    cleared[*j] = true;
+                    fitness[*j] = worst;
+                }
+            }
+        }
+        fitness
+    }
+
+    //deterministic crowding: the children of each pair of parents (indexes into the population) are matched
+    //with the closest parents and replace them if they are not worse, the pairs must be disjoint
+    pub fn crowding(&self, population: &mut [Chromosome], families: Vec<(usize, usize, Chromosome, Chromosome)>, bounds: &[Bound],
+                minimization: bool) {
+
+        for (p1, p2, c1, c2) in families {
+
+            let straight: f64 = normalized_distance(&population[p1], &c1, bounds) + normalized_distance(&population[p2], &c2, bounds);
+            let crossed: f64 = normalized_distance(&population[p1], &c2, bounds) + normalized_distance(&population[p2], &c1, bounds);
+            let matches: [(usize, Chromosome); 2] = if straight <= crossed { [(p1, c1), (p2, c2)] } else { [(p1, c2), (p2, c1)] };
+
+            for (parent, child) in matches {
+                if !is_fitter(&population[parent], &child, minimization) {
+                    population[parent] = child;
+                }
+            }
+        }
+        sort_population(population, minimization);
+    }
+
+    //niches of a population, each individual joins the niche of the first better individual within the radius
+    pub fn niches(&self, population: &[Chromosome], bounds: &[Bound], minimization: bool) -> Vec<Niche> {
+
+        let mut sorted: Vec<Chromosome> = population.iter().filter(|c| c.fitness.is_finite()).cloned().collect();
+        sort_population(&mut sorted, minimization);
+
+        let mut niches: Vec<Niche> = vec![];
+        for c in sorted {
+            match niches.iter_mut().find(|n| normalized_distance(&n.best, &c, bounds) < self.radius) {
+                Some(niche) => niche.size += 1,
+                None => niches.push(Niche { best: c, size: 1 }),
+            }
+        }
+        niches
+    }
+}
+
+//order from the best to the worst fitness
+fn compare(a: f64, b: f64, minimization: bool) -> Ordering {
+    if is_better(a, b, minimization) {
+        Ordering::Less
+    }
+    else if is_better(b, a, minimization) {
+        Ordering::Greater
+    }
+    else {
+        Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ga::GA;
+    use super::super::ga_json::GA_Metadata;
+    use super::super::observer::ConsoleObserver;
+    use super::super::optimizer::Optimizer;
+
+    fn individual(x: f64, fitness: f64) -> Chromosome {
+        let mut c: Chromosome = Chromosome::new(vec![x]);
+        c.set_fitness(fitness);
+        c
+    }
+
+    fn bounds() -> Vec<Bound> {
+        vec![Bound::new(String::from("x"), -2.0, 2.0)]
+    }
+
+    #[test]
+    fn sharing_penalizes_a_crowded_niche_with_zero_fitness() {
+        let population: Vec<Chromosome> = vec![individual(1.0, 0.0), individual(1.0, 0.0), individual(1.0, 0.0), individual(-1.0, 0.0)];
+        let adjusted: Vec<Chromosome> = Niching::default().adjusted(&population, &bounds(), true);
+        assert_eq!(adjusted[0].fitness, 2.0);
+        assert_eq!(adjusted[3].fitness, 0.0);
+        assert_eq!(population[0].fitness, 0.0);
+    }
+
+    #[test]
+    fn crowding_replaces_the_closest_parent_when_the_child_is_not_worse() {
+        let mut population: Vec<Chromosome> = vec![individual(-1.0, 0.5), individual(1.0, 0.5), individual(0.0, 0.1)];
+        let families = vec![(0, 1, individual(0.9, 0.2), individual(-0.9, 0.7))];
+        Niching { method: NichingMethod::Crowding, ..Default::default() }.crowding(&mut population, families, &bounds(), true);
+
+        let values: Vec<f64> = population.iter().map(|c| c.values()[0]).collect();
+        assert_eq!(values, vec![0.0, 0.9, -1.0]);
+    }
+
+    #[test]
+    fn sharing_and_crowding_keep_both_optima_of_a_symmetric_function() {
+        let objective = (|| (), |_: &mut (), values: &[f64]| (values[0]*values[0] - 1.0).powi(2));
+
+        for (method, seed) in [NichingMethod::Sharing, NichingMethod::Crowding].into_iter().flat_map(|m| (1..=3).map(move |s| (m, s))) {
+            let niching: Niching = Niching { method, radius: 0.2, ..Default::default() };
+            let metadata: GA_Metadata = GA_Metadata {
+                population_size: 40, crossover_rate: 0.5, mutation_rate: 0.5, max_iterations: 40, seed: Some(seed), threads: Some(1),
+                elitism: 1, niching: Some(niching.clone()),
+                ..Default::default()
+            };
+            let result = GA::from_metadata(&metadata, bounds(), true).optimize(&objective, &mut ConsoleObserver).unwrap();
+
+            let optima: Vec<&Niche> = result.niches.iter().filter(|n| n.best.fitness < 1e-2).collect();
+            assert!(optima.iter().any(|n| n.best.values()[0] < 0.0), "{:?} seed {}: {:?}", method, seed, result.niches);
+            assert!(optima.iter().any(|n| n.best.values()[0] > 0.0), "{:?} seed {}: {:?}", method, seed, result.niches);
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::Error;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use super::crossover::Crossover;
+use super::ga::{is_better, recombine, Chromosome};
+use super::ga_json::{Bound, GA_Metadata};
+use super::hall_of_fame::HallOfFameConfig;
+use super::history::History;
+use super::initialization::Initialization;
+use super::mutation::Mutation;
+use super::objective::{thread_pool, Objective};
+use super::observer::Observer;
+use super::optimizer::{run_seed, OptimizationResult, Optimizer, RunTracker};
+use super::termination::{StopReason, Termination};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Nsga2Parameters {
+    //labels of the data columns of each objective, e.g. [["I"], ["S", "R"]], every column is an objective if empty
+    pub groups: Vec<Vec<String>>,
+    //CSV file where the Pareto front is written at the end of the run
+    pub front_file: Option<String>,
+}
+
+//non-dominated solution of a multi-objective run
+#[derive(Debug, Clone)]
+pub struct ParetoSolution {
+    pub chromosome: Chromosome, //its fitness summarizes the objectives
+    pub objectives: Vec<f64>,
+    pub crowding_distance: f64, //infinite at the extremes of the front
+}
+
+#[derive(Debug, Clone)]
+struct Individual {
+    chromosome: Chromosome,
+    objectives: Vec<f64>,
+    rank: usize, //index of its front, 0 is the non-dominated one
+    crowding: f64,
+}
+
+impl Individual {
+
+    fn new(chromosome: Chromosome) -> Self {
+        Self {
+            chromosome,
+            objectives: vec![],
+            rank: 0,
+            crowding: 0.0,
+        }
+    }
+
+    //crowded comparison: lower rank first, then the less crowded one
+    fn is_preferred(&self, other: &Individual) -> bool {
+        self.rank < other.rank || (self.rank == other.rank && self.crowding > other.crowding)
+    }
+}
+
+//true if `a` is not worse than `b` in any objective and better in at least one
+fn dominates(a: &[f64], b: &[f64], minimization: bool) -> bool {
+    a.iter().zip(b.iter()).all(|(x, y)| !is_better(*y, *x, minimization))
+        && a.iter().zip(b.iter()).any(|(x, y)| is_better(*x, *y, minimization))
+}
+
+//constrained domination (Deb): the smaller violation of the constraints dominates, the objectives decide
+//between individuals with the same violation
+fn constrained_dominates(a: &Individual, b: &Individual, minimization: bool) -> bool {
+    let (va, vb) = (a.chromosome.violation, b.chromosome.violation);
+    if va != vb {
+        return va < vb;
+    }
+    dominates(&a.objectives, &b.objectives, minimization)
+}
+
+//fronts of the population (indexes), sets the rank and the crowding distance of every individual
+fn non_dominated_sort(individuals: &mut [Individual], minimization: bool) -> Vec<Vec<usize>> {
+
+    let n: usize = individuals.len();
+    let mut dominated: Vec<Vec<usize>> = vec![vec![]; n]; //individuals dominated by each one
+    let mut counts: Vec<usize> = vec![0; n]; //number of individuals that dominate each one
+
+    for i in 0..n {
+        for j in i + 1..n {
+            if constrained_dominates(&individuals[i], &individuals[j], minimization) {
+                dominated[i].push(j);
+                counts[j] += 1;
+            }
+            else if constrained_dominates(&individuals[j], &individuals[i], minimization) {
+                dominated[j].push(i);
+                counts[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts: Vec<Vec<usize>> = vec![];
+    let mut front: Vec<usize> = (0..n).filter(|i| counts[*i] == 0).collect();
+
+    while !front.is_empty() {
+        let mut next: Vec<usize> = vec![];
+        for i in front.iter() {
+            individuals[*i].rank = fronts.len();
+            for j in dominated[*i].iter() {
+                counts[*j] -= 1;
+                if counts[*j] == 0 {
+                    next.push(*j);
+                }
+            }
+        }
+        crowding_distance(individuals, &front);
+        fronts.push(front);
+        front = next;
+    }
+    fronts
+}
+
+//individuals of the list without an earlier one with the same objectives
+fn distinct(individuals: &[Individual], indexes: &[usize]) -> Vec<usize> {
+    indexes
+        .iter()
+        .enumerate()
+        .filter(|(k, i)| !indexes[..*k].iter().any(|j| individuals[*j].objectives == individuals[**i].objectives))
+        .map(|(_, i)| *i)
+        .collect()
+}
+
+//sum over the objectives of the normalized distance between the neighbours of each individual of the front,
+//the copies of a point have no crowding so they are the first left out of the population
+fn crowding_distance(individuals: &mut [Individual], front: &[usize]) {
+
+    for i in front.iter() {
+        individuals[*i].crowding = 0.0;
+    }
+
+    let front: Vec<usize> = distinct(individuals, front);
+    let objectives: usize = front.first().map_or(0, |i| individuals[*i].objectives.len());
+    for m in 0..objectives {
+
+        let mut sorted: Vec<usize> = front.to_vec();
+        sorted.sort_by(|a, b| individuals[*a].objectives[m].partial_cmp(&individuals[*b].objectives[m]).unwrap_or(Ordering::Equal));
+
+        let (first, last) = (sorted[0], sorted[sorted.len() - 1]);
+        let range: f64 = individuals[last].objectives[m] - individuals[first].objectives[m];
+        individuals[first].crowding = f64::INFINITY;
+        individuals[last].crowding = f64::INFINITY;
+
+        if range <= 0.0 {
+            continue;
+        }
+        for k in 1..sorted.len().saturating_sub(1) {
+            let distance: f64 = (individuals[sorted[k + 1]].objectives[m] - individuals[sorted[k - 1]].objectives[m])/range;
+            individuals[sorted[k]].crowding += distance;
+        }
+    }
+}
+
+//calculates the objectives of each individual, in parallel when the pool has more than one thread
+fn evaluate<O: Objective>(pool: &ThreadPool, individuals: &mut [Individual], objective: &O) -> usize {
+
+    let assign = |worker: &mut O::Worker, individual: &mut Individual| {
+        let ((fitness, objectives), evaluations) = objective.objectives_counted(worker, individual.chromosome.values());
+        individual.chromosome.violation = objective.violation(worker, individual.chromosome.values());
+        individual.chromosome.set_fitness(fitness);
+        individual.objectives = objectives;
+        evaluations
+    };
+
+    if pool.current_num_threads() <= 1 {
+        let mut worker: O::Worker = objective.worker();
+        return individuals.iter_mut().map(|individual| assign(&mut worker, individual)).sum();
+    }
+
+    pool.install(|| {
+        individuals
+            .par_iter_mut()
+            .map_init(|| objective.worker(), |worker, individual| assign(worker, individual))
+            .sum()
+    })
+}
+
+//NSGA-II (Deb et al., 2002), the offspring is created with the crossover and mutation operators of the GA
+//configuration and the parents and children compete for survival by front and crowding distance
+//the objectives are given by the objective function (see Nsga2Parameters for the data fitting)
+#[derive(Debug, Clone)]
+pub struct Nsga2 {
+    bounds: Vec<Bound>,
+    minimization: bool,
+    population_size: usize,
+    max_generations: usize,
+    crossover_rate: f64,
+    mutation_rate: f64,
+    crossover_operator: Crossover,
+    mutation_operator: Mutation,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    initialization: Initialization,
+    threads: Option<usize>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    population: Vec<Individual>,
+    history: History,
+}
+
+impl Nsga2 {
+
+    pub fn from_metadata(metadata: &GA_Metadata, bounds: Vec<Bound>, is_min: bool) -> Self {
+        let seed: u64 = run_seed(metadata);
+        Self {
+            bounds,
+            minimization: is_min,
+            population_size: metadata.population_size,
+            max_generations: metadata.max_iterations,
+            crossover_rate: metadata.crossover_rate,
+            mutation_rate: metadata.mutation_rate,
+            crossover_operator: metadata.crossover.clone(),
+            mutation_operator: metadata.mutation.clone(),
+            termination: metadata.termination.clone(),
+            hall_of_fame: metadata.hall_of_fame.clone(),
+            initialization: metadata.initialization.clone(),
+            threads: metadata.threads,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            population: vec![],
+            history: History::default(),
+        }
+    }
+
+    //binary tournament with the crowded comparison
+    fn tournament(&mut self) -> usize {
+        let a: usize = self.rng.gen_range(0..self.population.len());
+        let b: usize = self.rng.gen_range(0..self.population.len());
+        if self.population[b].is_preferred(&self.population[a]) { b } else { a }
+    }
+
+    fn offspring(&mut self) -> Vec<Individual> {
+
+        let mut offspring: Vec<Individual> = vec![];
+
+        while offspring.len() < self.population_size {
+            let (p1, p2) = (self.tournament(), self.tournament());
+            let (mut c1, mut c2) = recombine(&self.population[p1].chromosome, &self.population[p2].chromosome,
+                &self.crossover_operator, self.crossover_rate, &self.bounds, &mut self.rng);
+
+            c1.mutation(&self.mutation_operator, self.mutation_rate, &self.bounds, &mut self.rng);
+            c2.mutation(&self.mutation_operator, self.mutation_rate, &self.bounds, &mut self.rng);
+            offspring.push(Individual::new(c1));
+            offspring.push(Individual::new(c2));
+        }
+        offspring.truncate(self.population_size);
+        offspring
+    }
+
+    //the best fronts of parents and children, the last one that fits partially keeps its less crowded individuals
+    fn survivors(&self, mut candidates: Vec<Individual>) -> Vec<Individual> {
+
+        let fronts: Vec<Vec<usize>> = non_dominated_sort(&mut candidates, self.minimization);
+        let mut selected: Vec<usize> = vec![];
+
+        for mut front in fronts {
+            if selected.len() + front.len() > self.population_size {
+                front.sort_by(|a, b| candidates[*b].crowding.partial_cmp(&candidates[*a].crowding).unwrap_or(Ordering::Equal));
+                front.truncate(self.population_size - selected.len());
+            }
+            selected.append(&mut front);
+            if selected.len() == self.population_size {
+                break;
+            }
+        }
+
+        selected.iter().map(|i| candidates[*i].clone()).collect()
+    }
+
+    fn chromosomes(&self) -> Vec<Chromosome> {
+        self.population.iter().map(|i| i.chromosome.clone()).collect()
+    }
+
+    //first front of the population without repeated points, ordered by the first objective
+    fn pareto_front(&self) -> Vec<ParetoSolution> {
+
+        let first: Vec<usize> = (0..self.population.len()).filter(|i| self.population[*i].rank == 0).collect();
+        let mut front: Vec<ParetoSolution> = distinct(&self.population, &first)
+            .into_iter()
+            .map(|i| &self.population[i])
+            .map(|i| ParetoSolution { chromosome: i.chromosome.clone(), objectives: i.objectives.clone(), crowding_distance: i.crowding })
+            .collect();
+
+        front.sort_by(|a, b| a.objectives.first().partial_cmp(&b.objectives.first()).unwrap_or(Ordering::Equal));
+        if !self.minimization {
+            front.reverse();
+        }
+        front
+    }
+}
+
+impl<O: Objective> Optimizer<O> for Nsga2 {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> {
+
+        let start: Instant = Instant::now();
+
+        let pool: ThreadPool = thread_pool(self.threads)?;
+
+        self.population = self.initialization.population(&self.bounds, self.population_size, &mut self.rng)?
+            .into_iter()
+            .map(Individual::new)
+            .collect();
+        let evaluations: usize = evaluate(&pool, &mut self.population, objective);
+        non_dominated_sort(&mut self.population, self.minimization);
+
+        let mut tracker: RunTracker = RunTracker::new(start, &self.chromosomes(), &self.bounds, evaluations, self.minimization, self.hall_of_fame.as_ref(), observer)?;
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(reason) = tracker.next_iteration(&self.termination, self.max_generations, observer) {
+                break reason;
+            }
+
+            let mut offspring: Vec<Individual> = self.offspring();
+            tracker.evaluations += evaluate(&pool, &mut offspring, objective);
+
+            let mut candidates: Vec<Individual> = std::mem::take(&mut self.population);
+            candidates.append(&mut offspring);
+            self.population = self.survivors(candidates);
+
+            tracker.end_iteration(&self.chromosomes(), &self.bounds, observer);
+        };
+
+        let mut result: OptimizationResult = tracker.finish(stop_reason, observer);
+        result.pareto_front = self.pareto_front();
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
+}
+
+//one line per solution with the values of the parameters, the objectives, the fitness and the crowding distance
+pub fn save_pareto_front<P: AsRef<Path>>(path: P, front: &[ParetoSolution], bounds: &[Bound], objectives: &[String]) -> anyhow::Result<(),Error> {
+
+    let mut writer = csv::Writer::from_path(path)?;
+
+    let mut header: Vec<String> = bounds.iter().map(|b| b.name.clone()).collect();
+    header.extend(objectives.iter().cloned());
+    header.push(String::from("fitness"));
+    header.push(String::from("crowding_distance"));
+    writer.write_record(&header)?;
+
+    for solution in front.iter() {
+        let mut record: Vec<String> = solution.chromosome.values().iter().map(|v| v.to_string()).collect();
+        record.extend(solution.objectives.iter().map(|v| v.to_string()));
+        record.push(solution.chromosome.fitness.to_string());
+        record.push(solution.crowding_distance.to_string());
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn individuals(points: &[[f64; 2]]) -> Vec<Individual> {
+        points
+            .iter()
+            .map(|p| {
+                let mut i: Individual = Individual::new(Chromosome::new(p.to_vec()));
+                i.objectives = p.to_vec();
+                i
+            })
+            .collect()
+    }
+
+    fn sorted(fronts: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+        fronts.into_iter().map(|mut f| { f.sort(); f }).collect()
+    }
+
+    const POINTS: [[f64; 2]; 5] = [[1.0, 5.0], [2.0, 3.0], [4.0, 1.0], [3.0, 4.0], [5.0, 5.0]];
+
+    #[test]
+    fn dominance() {
+        assert!(dominates(&[1.0, 2.0], &[1.0, 3.0], true));
+        assert!(!dominates(&[1.0, 2.0], &[1.0, 2.0], true));
+        assert!(!dominates(&[1.0, 4.0], &[2.0, 3.0], true));
+        assert!(dominates(&[1.0, 3.0], &[1.0, 2.0], false));
+    }
+
+    #[test]
+    fn fronts_minimization() {
+        let mut population: Vec<Individual> = individuals(&POINTS);
+        let fronts: Vec<Vec<usize>> = non_dominated_sort(&mut population, true);
+        assert_eq!(sorted(fronts), vec![vec![0, 1, 2], vec![3], vec![4]]);
+        assert_eq!(population.iter().map(|i| i.rank).collect::<Vec<usize>>(), vec![0, 0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn fronts_maximization() {
+        let mut population: Vec<Individual> = individuals(&POINTS);
+        let fronts: Vec<Vec<usize>> = non_dominated_sort(&mut population, false);
+        assert_eq!(sorted(fronts), vec![vec![4], vec![0, 2, 3], vec![1]]);
+    }
+
+    #[test]
+    fn crowding_of_the_first_front() {
+        let mut population: Vec<Individual> = individuals(&POINTS);
+        crowding_distance(&mut population, &[0, 1, 2]);
+        //the middle point: (4 - 1)/3 in the first objective and (5 - 1)/4 in the second
+        assert_eq!(population[1].crowding, 2.0);
+        assert!(population[0].crowding.is_infinite() && population[2].crowding.is_infinite());
+
+        crowding_distance(&mut population, &[3]);
+        assert!(population[3].crowding.is_infinite());
+    }
+
+    #[test]
+    fn crowding_of_equal_points() {
+        let mut population: Vec<Individual> = individuals(&[[1.0, 1.0], [1.0, 1.0], [1.0, 1.0]]);
+        crowding_distance(&mut population, &[0, 1, 2]);
+        assert!(population[0].crowding.is_infinite());
+        assert_eq!(population[1].crowding, 0.0);
+        assert_eq!(population[2].crowding, 0.0);
+    }
+
+    #[test]
+    fn copies_are_left_out_first_and_not_exported() {
+        let points: [[f64; 2]; 6] = [[1.0, 5.0], [2.0, 3.0], [2.0, 3.0], [2.0, 3.0], [4.0, 1.0], [3.0, 2.0]];
+        let mut nsga2: Nsga2 = Nsga2::from_metadata(&GA_Metadata { population_size: 4, ..Default::default() }, vec![], true);
+
+        //the copies of [2, 3] are the most crowded of the first front, the extremes are kept first
+        nsga2.population = nsga2.survivors(individuals(&points));
+        let kept: Vec<Vec<f64>> = nsga2.population.iter().map(|i| i.objectives.clone()).collect();
+        assert_eq!(kept, vec![vec![1.0, 5.0], vec![4.0, 1.0], vec![2.0, 3.0], vec![3.0, 2.0]]);
+
+        nsga2.population = individuals(&points);
+        non_dominated_sort(&mut nsga2.population, true);
+        let front: Vec<Vec<f64>> = nsga2.pareto_front().into_iter().map(|s| s.objectives).collect();
+        assert_eq!(front, vec![vec![1.0, 5.0], vec![2.0, 3.0], vec![3.0, 2.0], vec![4.0, 1.0]]);
+    }
+}
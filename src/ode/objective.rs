@@ -0,0 +1,269 @@
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::ThreadPool;
+
+use super::csvdata::CSVData;
+use super::ga::Chromosome;
+use super::ga_json::ConfigData;
+use super::model::{create_ode_system, solve, OdeSystem, State};
+
+//function minimized (or maximized) by the optimizers
+//every thread creates its own worker (e.g. a copy of the ODE system) and reuses it for all its evaluations
+pub trait Objective: Sync {
+    type Worker;
+
+    fn worker(&self) -> Self::Worker;
+
+    fn evaluate(&self, worker: &mut Self::Worker, values: &[f64]) -> f64;
+
+    //individual errors whose sum of squares is minimized, None if the objective is not a least-squares problem
+    //or if they could not be calculated
+    fn residuals(&self, _worker: &mut Self::Worker, _values: &[f64]) -> Option<Vec<f64>> {
+        None
+    }
+
+    //fitness and values of the objectives of a multi-objective problem, the fitness is the only objective by default
+    fn objectives(&self, worker: &mut Self::Worker, values: &[f64]) -> (f64, Vec<f64>) {
+        let fitness: f64 = self.evaluate(worker, values);
+        (fitness, vec![fitness])
+    }
+
+    //fitness and number of calculations of the objective it took, 0 if the value was remembered (see Cached)
+    fn evaluate_counted(&self, worker: &mut Self::Worker, values: &[f64]) -> (f64, usize) {
+        (self.evaluate(worker, values), 1)
+    }
+
+    fn objectives_counted(&self, worker: &mut Self::Worker, values: &[f64]) -> ((f64, Vec<f64>), usize) {
+        (self.objectives(worker, values), 1)
+    }
+
+    //violation of the constraints compared before the fitness (see is_fitter), 0 if there are no constraints
+    //or if they are handled in the fitness
+    fn violation(&self, _worker: &mut Self::Worker, _values: &[f64]) -> f64 {
+        0.0
+    }
+}
+
+//a pair of closures: the first creates the worker of a thread, the second calculates the fitness
+impl<S, I, F> Objective for (I, F)
+where I: Fn() -> S + Sync, F: Fn(&mut S, &[f64]) -> f64 + Sync
+{
+    type Worker = S;
+
+    fn worker(&self) -> S {
+        (self.0)()
+    }
+
+    fn evaluate(&self, worker: &mut S, values: &[f64]) -> f64 {
+        (self.1)(worker, values)
+    }
+}
+
+//None uses every available core
+pub fn thread_pool(threads: Option<usize>) -> Result<ThreadPool,()> {
+    match rayon::ThreadPoolBuilder::new().num_threads(threads.unwrap_or(0)).build() {
+        Ok(p) => Ok(p),
+        Err(e) => { println!("Could not create the thread pool: {:?}", e); Err(()) },
+    }
+}
+
+//calculates the fitness of an individual, returns the number of evaluations of the objective (0 for a cached value)
+pub fn evaluate_individual<O: Objective>(objective: &O, worker: &mut O::Worker, c: &mut Chromosome) -> usize {
+    let (fitness, evaluations) = objective.evaluate_counted(worker, c.values());
+    c.violation = objective.violation(worker, c.values());
+    c.set_fitness(fitness);
+    evaluations
+}
+
+//calculates the fitness of each individual that was not evaluated with its current values, in parallel
+//when the pool has more than one thread, returns the number of evaluations of the objective
+pub fn evaluate_population<O: Objective>(pool: &ThreadPool, individuals: &mut [Chromosome], objective: &O) -> usize {
+
+    let assign = |worker: &mut O::Worker, c: &mut Chromosome| evaluate_individual(objective, worker, c);
+
+    if pool.current_num_threads() <= 1 {
+        let mut worker: O::Worker = objective.worker();
+        return individuals
+            .iter_mut()
+            .filter(| c | !c.is_evaluated())
+            .map(| c | assign(&mut worker, c))
+            .sum();
+    }
+
+    pool.install(|| {
+        individuals
+            .par_iter_mut()
+            .filter(| c | !c.is_evaluated())
+            .map_init(|| objective.worker(), | worker, c | assign(worker, c))
+            .sum()
+    })
+}
+
+//root of the sum of the squared differences between the solution of the ODE system and the experimental data
+pub struct DataFitting {
+    source: String,
+    config_data: ConfigData,
+    data: CSVData,
+    indexes: Vec<usize>, //position in the state vector of each data column
+    y: State, //initial condition
+    groups: Vec<Vec<usize>>, //data columns of each objective of a multi-objective fitting
+}
+
+impl DataFitting {
+
+    pub fn new(ode_system: &OdeSystem, data: CSVData) -> Self {
+
+        let mut indexes: Vec<usize> = vec![];
+        for label in data.labels.iter() {
+            for (pop_index, key) in ode_system.equations.keys().enumerate() {
+                if label.trim() == key.trim() {
+                    indexes.push(pop_index);
+                }
+            }
+        }
+
+        let y: State = State::from_vec(ode_system.equations.keys()
+                .map(|k| ode_system.get_argument_value(k.to_string())).collect());
+
+        Self {
+            source: ode_system.source.clone(),
+            config_data: ode_system.config_data.clone(),
+            groups: (0..data.labels.len()).map(|i| vec![i]).collect(),
+            indexes,
+            y,
+            data,
+        }
+    }
+
+    //objectives made of groups of data columns, every column is an objective if there are no groups
+    pub fn with_groups(mut self, groups: &[Vec<String>]) -> Result<Self,()> {
+
+        if !groups.is_empty() {
+            let mut columns: Vec<Vec<usize>> = vec![];
+            for group in groups.iter() {
+                let mut indexes: Vec<usize> = vec![];
+                for label in group.iter() {
+                    match self.data.labels.iter().position(|l| l.trim() == label.trim()) {
+                        Some(i) => indexes.push(i),
+                        None => { println!("The data file has no column {}", label); return Err(()); },
+                    }
+                }
+                columns.push(indexes);
+            }
+            self.groups = columns;
+        }
+
+        if self.groups.is_empty() || self.groups.iter().any(|g| g.is_empty()) {
+            println!("Every objective needs at least one data column");
+            return Err(());
+        }
+        Ok(self)
+    }
+
+    //names of the objectives, the labels of their data columns
+    pub fn objective_names(&self) -> Vec<String> {
+        self.groups
+            .iter()
+            .map(|g| g.iter().map(|i| self.data.labels[*i].trim()).collect::<Vec<&str>>().join("+"))
+            .collect()
+    }
+
+    //squared error of each data column
+    fn column_errors(&self, system: &mut OdeSystem, values: &[f64]) -> Option<Vec<f64>> {
+
+        let residuals: Vec<f64> = self.residuals(system, values)?;
+
+        let columns: usize = self.data.labels.len().max(1);
+        Some(residuals
+            .chunks(residuals.len().div_ceil(columns).max(1))
+            .map(|column| column.iter().map(|d| d*d).sum())
+            .collect())
+    }
+}
+
+impl Objective for DataFitting {
+    type Worker = OdeSystem;
+
+    fn worker(&self) -> OdeSystem {
+        let mut system: OdeSystem = create_ode_system(self.source.clone(), &self.config_data);
+        system.update_context_with_state(&self.y);
+        system
+    }
+
+    fn evaluate(&self, system: &mut OdeSystem, values: &[f64]) -> f64 {
+
+        let errors: Vec<f64> = match self.column_errors(system, values) {
+            Some(e) => e,
+            None => return 1000.0,
+        };
+
+        let sum: f64 = errors.iter().sum();
+        if sum.is_nan(){
+            return 1000.0;
+        }
+
+        sum.sqrt()
+    }
+
+    //root of the squared error of each group of columns
+    fn objectives(&self, system: &mut OdeSystem, values: &[f64]) -> (f64, Vec<f64>) {
+
+        let errors: Vec<f64> = match self.column_errors(system, values) {
+            Some(e) if e.len() == self.data.labels.len() => e,
+            _ => return (1000.0, vec![1000.0; self.groups.len()]),
+        };
+
+        let sum: f64 = errors.iter().sum();
+        let fitness: f64 = if sum.is_nan() { 1000.0 } else { sum.sqrt() };
+
+        let objectives: Vec<f64> = self.groups
+            .iter()
+            .map(|g| g.iter().map(|i| errors[*i]).sum::<f64>().sqrt())
+            .map(|e| if e.is_nan() { 1000.0 } else { e })
+            .collect();
+
+        (fitness, objectives)
+    }
+
+    //differences between the solution and the data, all the times of the first column, then of the second...
+    fn residuals(&self, system: &mut OdeSystem, values: &[f64]) -> Option<Vec<f64>> {
+
+        let mut errors: Vec<Vec<f64>> = vec![vec![]; self.data.labels.len()];
+
+        system.update_context(values);
+
+        let ode_result: Vec<State> = solve(system, &self.y);
+        if ode_result.is_empty() {
+            return None;
+        }
+
+        let mut index: usize = 0;
+        let mut ode_index: usize = 0;
+        let mut t: f64 = self.config_data.metadata.start_time;
+        let dt: f64 = self.config_data.metadata.delta_time;
+        let t_end = self.config_data.metadata.end_time;
+
+        while t <= t_end {
+
+            if index == self.data.time.len() {
+                break;
+            }
+
+            if  (t - self.data.time[index]).abs() < 10.0_f64.powf(-6.0) {
+
+                for (i, error) in errors.iter_mut().enumerate() {
+
+                    let data: f64 = self.data.lines[i][index];
+
+                    error.push(ode_result[ode_index][self.indexes[i]] - data);
+                }
+
+                index += 1;
+            }
+
+            t += dt;
+            ode_index += 1;
+        }
+
+        Some(errors.concat())
+    }
+}
@@ -0,0 +1,49 @@
+use super::ga::Chromosome;
+use super::history::GenerationStats;
+use super::optimizer::OptimizationResult;
+
+//answer of an observer, Stop cancels the optimization at the end of the current step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+//receives the progress of an optimization, every method has an empty default implementation
+pub trait Observer {
+
+    fn on_generation_start(&mut self, _generation: usize) -> Control {
+        Control::Continue
+    }
+
+    fn on_generation_end(&mut self, _stats: &GenerationStats, _best: &Chromosome) -> Control {
+        Control::Continue
+    }
+
+    fn on_new_best(&mut self, _generation: usize, _best: &Chromosome) -> Control {
+        Control::Continue
+    }
+
+    fn on_termination(&mut self, _result: &OptimizationResult) {}
+}
+
+//prints the progress to stdout
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleObserver;
+
+impl Observer for ConsoleObserver {
+
+    fn on_generation_start(&mut self, generation: usize) -> Control {
+        println!("iteration {:?}: ", generation);
+        Control::Continue
+    }
+
+    fn on_generation_end(&mut self, _stats: &GenerationStats, best: &Chromosome) -> Control {
+        println!("current best is {:?}", best);
+        Control::Continue
+    }
+
+    fn on_termination(&mut self, result: &OptimizationResult) {
+        println!("stopped after {} generations: {}", result.generations, result.stop_reason);
+    }
+}
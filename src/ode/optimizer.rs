@@ -0,0 +1,333 @@
+use std::time::{Duration, Instant};
+
+use nalgebra::DMatrix;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use super::checkpoint::{config_hash, Checkpoint};
+use super::cmaes::{Cmaes, CmaesParameters};
+use super::de::{DeParameters, DifferentialEvolution};
+use super::ga::{is_fitter, Chromosome, GA};
+use super::ga_json::{Bound, ConfigData, GA_Metadata};
+use super::hall_of_fame::{HallOfFame, HallOfFameConfig};
+use super::history::{GenerationStats, History, Refinement, Restart};
+use super::islands::IslandModel;
+use super::levenberg_marquardt::{LevenbergMarquardt, LmParameters};
+use super::objective::Objective;
+use super::nelder_mead::{NelderMead, NelderMeadParameters};
+use super::niching::Niche;
+use super::nsga2::{Nsga2, Nsga2Parameters, ParetoSolution};
+use super::observer::{Control, Observer};
+use super::pso::{ParticleSwarm, PsoParameters};
+use super::termination::{Progress, StopReason, Termination};
+
+//algorithm used to estimate the parameters, the population size, iterations, termination, threads and seed
+//of the GA configuration are shared by all of them
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Algorithm {
+    #[default]
+    #[serde(rename = "genetic_algorithm")]
+    Genetic,
+    DifferentialEvolution(DeParameters),
+    Cmaes(CmaesParameters),
+    ParticleSwarm(PsoParameters),
+    NelderMead(NelderMeadParameters),
+    //minimizes the sum of squares of the residuals of the objective
+    LevenbergMarquardt(LmParameters),
+    //multi-objective, each data column (or group of columns) is an objective
+    Nsga2(Nsga2Parameters),
+}
+
+#[derive(Debug,Clone)]
+pub struct OptimizationResult {
+    pub best: Chromosome,
+    pub stop_reason: StopReason,
+    pub generations: usize, //iterations of the algorithm
+    pub evaluations: usize,
+    pub history: History,
+    pub covariance: Option<DMatrix<f64>>, //estimated covariance of the parameters, if the algorithm provides one
+    pub pareto_front: Vec<ParetoSolution>, //non-dominated solutions of a multi-objective run
+    pub hall_of_fame: Vec<Chromosome>, //best distinct solutions of the run, if it was configured
+    pub niches: Vec<Niche>, //distinct optima of the final population of a GA with niching
+}
+
+pub trait Optimizer<O: Objective> {
+
+    fn bounds(&self) -> &[Bound];
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()>;
+
+    //statistics of the last run
+    fn history(&self) -> &History;
+
+    fn seed(&self) -> u64;
+
+    //prepares the next run to continue from a checkpoint, returns false if the algorithm does not support it
+    fn resume(&mut self, _checkpoint: Checkpoint) -> bool {
+        false
+    }
+}
+
+//seed of a run, a random one if the configuration has none
+pub fn run_seed(metadata: &GA_Metadata) -> u64 {
+    metadata.seed.unwrap_or_else(|| rand::thread_rng().next_u64())
+}
+
+pub fn create_optimizer<O: Objective>(config_data: &ConfigData, minimization: bool) -> Result<Box<dyn Optimizer<O>>,()> {
+
+    //the optimizers work in the search space of the bounds, the seed individuals are encoded into it
+    let mut metadata: GA_Metadata = config_data.metadata.clone();
+    metadata.initialization.encoding = config_data.bounds.clone();
+    let metadata = &metadata;
+    let bounds: Vec<Bound> = config_data.bounds.iter().map(Bound::encoded).collect();
+
+    let optimizer: Box<dyn Optimizer<O>> = match &metadata.algorithm {
+        Algorithm::Genetic => match &metadata.islands {
+            //the islands only evolve and migrate, the settings of a whole GA run are not applied to them
+            Some(_) if metadata.local_search.is_some() || metadata.restart.is_some() || metadata.checkpoint.is_some() => {
+                println!("The island model does not support local_search, restart or checkpoint, remove them or the islands from the configuration");
+                return Err(());
+            },
+            Some(islands) => Box::new(IslandModel::from_metadata(metadata, islands.clone(), bounds, minimization)),
+            None => Box::new(GA::from_metadata(metadata, bounds, minimization).with_config_hash(config_hash(config_data))),
+        },
+        Algorithm::DifferentialEvolution(parameters) => Box::new(
+            DifferentialEvolution::from_metadata(metadata, parameters.clone(), bounds, minimization)
+        ),
+        Algorithm::Cmaes(parameters) => Box::new(
+            Cmaes::from_metadata(metadata, parameters.clone(), bounds, minimization)
+        ),
+        Algorithm::ParticleSwarm(parameters) => Box::new(
+            ParticleSwarm::from_metadata(metadata, parameters.clone(), bounds, minimization)?
+        ),
+        Algorithm::NelderMead(parameters) => Box::new(
+            NelderMead::from_metadata(metadata, parameters.clone(), bounds, minimization)
+        ),
+        Algorithm::LevenbergMarquardt(parameters) => Box::new(
+            LevenbergMarquardt::from_metadata(metadata, parameters.clone(), bounds)
+        ),
+        Algorithm::Nsga2(_) => Box::new(
+            Nsga2::from_metadata(metadata, bounds, minimization)
+        ),
+    };
+    Ok(optimizer)
+}
+
+//bookkeeping shared by the optimizers: best solution, counters, stopping rules, history and observer calls
+#[derive(Debug,Clone)]
+pub struct RunTracker {
+    start: Instant,
+    elapsed_before: Duration, //time spent before a resume
+    pub iteration: usize,
+    pub evaluations: usize,
+    pub best: Chromosome,
+    pub progress: Progress,
+    pub history: History,
+    pub covariance: Option<DMatrix<f64>>,
+    pub hall_of_fame: Option<HallOfFame>,
+    control: Control,
+    minimization: bool,
+}
+
+impl RunTracker {
+
+    //starts a run from the evaluated initial population, `start` is the time before its evaluation
+    pub fn new(start: Instant, population: &[Chromosome], bounds: &[Bound], evaluations: usize, minimization: bool, 
+                hall_of_fame: Option<&HallOfFameConfig>, observer: &mut dyn Observer) -> Result<Self,()> {
+
+        let best: Chromosome = best_of(population, minimization).ok_or(())?.clone();
+
+        let mut tracker: RunTracker = RunTracker {
+            start,
+            elapsed_before: Duration::ZERO,
+            iteration: 0,
+            evaluations,
+            progress: Progress::new(best.clone()),
+            best,
+            history: History::default(),
+            covariance: None,
+            hall_of_fame: hall_of_fame.map(|config| HallOfFame::new(config, bounds, minimization)),
+            control: Control::Continue,
+            minimization,
+        };
+        if let Some(hall_of_fame) = tracker.hall_of_fame.as_mut() {
+            hall_of_fame.update(population);
+        }
+        tracker.history.push(GenerationStats::from_population(
+            0, population, bounds, evaluations, start.elapsed().as_secs_f64(), minimization));
+        if observer.on_new_best(0, &tracker.best) == Control::Stop {
+            tracker.control = Control::Stop;
+        }
+
+        Ok(tracker)
+    }
+
+    //continues a run saved in a checkpoint
+    pub fn resume(checkpoint: &Checkpoint, bounds: &[Bound], minimization: bool, hall_of_fame: Option<&HallOfFameConfig>) -> Self {
+
+        let mut progress: Progress = Progress::new(checkpoint.best.clone());
+        progress.generations_without_improvement = checkpoint.generations_without_improvement;
+
+        let hall_of_fame: Option<HallOfFame> = hall_of_fame.map(|config| {
+            let mut h: HallOfFame = HallOfFame::new(config, bounds, minimization);
+            h.update(&checkpoint.hall_of_fame);
+            h
+        });
+
+        RunTracker {
+            start: Instant::now(),
+            elapsed_before: Duration::from_secs_f64(checkpoint.elapsed),
+            iteration: checkpoint.generation,
+            evaluations: checkpoint.evaluations,
+            best: checkpoint.best.clone(),
+            progress,
+            history: checkpoint.history.clone(),
+            covariance: None,
+            hall_of_fame,
+            control: Control::Continue,
+            minimization,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_before + self.start.elapsed()
+    }
+
+    //checks the stopping rules, if the run goes on the observer is notified of the start of the next iteration
+    pub fn next_iteration(&mut self, termination: &Termination, max_iterations: usize, observer: &mut dyn Observer) -> Option<StopReason> {
+
+        self.progress.generation = self.iteration;
+        self.progress.evaluations = self.evaluations;
+        self.progress.elapsed = self.elapsed();
+        self.progress.diversity = self.history.generations.last().map_or(f64::NAN, |s| s.diversity);
+
+        if self.control == Control::Stop {
+            return Some(StopReason::Cancelled);
+        }
+        if let Some(reason) = termination.check(&self.progress, max_iterations, self.minimization) {
+            return Some(reason);
+        }
+        if observer.on_generation_start(self.iteration) == Control::Stop {
+            return Some(StopReason::Cancelled);
+        }
+        None
+    }
+
+    //records the population at the end of an iteration
+    pub fn end_iteration(&mut self, population: &[Chromosome], bounds: &[Bound], observer: &mut dyn Observer) {
+
+        self.iteration += 1;
+
+        if let Some(hall_of_fame) = self.hall_of_fame.as_mut() {
+            hall_of_fame.update(population);
+        }
+
+        if let Some(current) = best_of(population, self.minimization) {
+            self.progress.update_best(current, self.minimization);
+            if is_fitter(current, &self.best, self.minimization) {
+                self.best = current.clone();
+                if observer.on_new_best(self.iteration, &self.best) == Control::Stop {
+                    self.control = Control::Stop;
+                }
+            }
+        }
+
+        let stats: GenerationStats = GenerationStats::from_population(
+            self.iteration, population, bounds, self.evaluations, self.elapsed().as_secs_f64(), self.minimization);
+        if observer.on_generation_end(&stats, &self.best) == Control::Stop {
+            self.control = Control::Stop;
+        }
+        self.history.push(stats);
+    }
+
+    //records a local search done on the population after the current iteration
+    pub fn refined(&mut self, refinement: Refinement, population: &[Chromosome], observer: &mut dyn Observer) {
+
+        self.evaluations += refinement.evaluations;
+        self.history.refinements.push(refinement);
+        self.changed(population, observer);
+    }
+
+    //records a restart of part of the population after the current iteration
+    pub fn restarted(&mut self, restart: Restart, population: &[Chromosome], observer: &mut dyn Observer) {
+
+        self.evaluations += restart.evaluations;
+        self.history.restarts.push(restart);
+        self.changed(population, observer);
+    }
+
+    //updates the best individual after the population changed outside an iteration
+    fn changed(&mut self, population: &[Chromosome], observer: &mut dyn Observer) {
+
+        if let Some(hall_of_fame) = self.hall_of_fame.as_mut() {
+            hall_of_fame.update(population);
+        }
+
+        if let Some(current) = best_of(population, self.minimization) {
+            if is_fitter(current, &self.best, self.minimization) {
+                self.progress.update_best(current, self.minimization);
+                self.best = current.clone();
+                if observer.on_new_best(self.iteration, &self.best) == Control::Stop {
+                    self.control = Control::Stop;
+                }
+            }
+        }
+    }
+
+    pub fn finish(self, stop_reason: StopReason, observer: &mut dyn Observer) -> OptimizationResult {
+
+        let result: OptimizationResult = OptimizationResult {
+            best: self.best,
+            stop_reason,
+            generations: self.iteration,
+            evaluations: self.evaluations,
+            history: self.history,
+            covariance: self.covariance,
+            pareto_front: vec![],
+            hall_of_fame: self.hall_of_fame.map_or(vec![], |h| h.members),
+            niches: vec![],
+        };
+        observer.on_termination(&result);
+        result
+    }
+}
+
+pub fn best_of(population: &[Chromosome], minimization: bool) -> Option<&Chromosome> {
+    population
+        .iter()
+        .reduce(|best, c| if is_fitter(c, best, minimization) { c } else { best })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::local_search::LocalSearch;
+
+    //cancels the run at the end of the first generation
+    struct StopAtFirstGeneration;
+
+    impl Observer for StopAtFirstGeneration {
+        fn on_generation_end(&mut self, _stats: &GenerationStats, _best: &Chromosome) -> Control {
+            Control::Stop
+        }
+    }
+
+    #[test]
+    fn stop_survives_new_best_of_local_search() {
+        let metadata: GA_Metadata = GA_Metadata {
+            population_size: 10, crossover_rate: 0.5, mutation_rate: 0.5, max_iterations: 20, seed: Some(1), threads: Some(1),
+            local_search: Some(LocalSearch { interval: Some(1), ..Default::default() }),
+            ..Default::default()
+        };
+        let bounds: Vec<Bound> = vec![Bound::new(String::from("x"), -5.0, 5.0), Bound::new(String::from("y"), -5.0, 5.0)];
+        let objective = (|| (), |_: &mut (), values: &[f64]| values.iter().map(|v| (v - 1.0)*(v - 1.0)).sum::<f64>());
+
+        let mut ga: GA = GA::from_metadata(&metadata, bounds, true);
+        let result: OptimizationResult = ga.optimize(&objective, &mut StopAtFirstGeneration).unwrap();
+
+        assert_eq!(result.stop_reason, StopReason::Cancelled);
+        assert_eq!(result.generations, 1);
+        assert!(!result.history.refinements.is_empty());
+    }
+}
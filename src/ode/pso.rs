@@ -0,0 +1,246 @@
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{is_fitter, Chromosome};
+use super::ga_json::{Bound, GA_Metadata};
+use super::hall_of_fame::HallOfFameConfig;
+use super::history::History;
+use super::initialization::Initialization;
+use super::objective::{evaluate_population, thread_pool, Objective};
+use super::observer::Observer;
+use super::optimizer::{run_seed, OptimizationResult, Optimizer, RunTracker};
+use super::termination::{StopReason, Termination};
+
+//which particles inform each particle
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    //the whole swarm (gbest)
+    #[default]
+    Global,
+    //the `neighbours` particles at each side of a ring (lbest)
+    Ring,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PsoParameters {
+    pub topology: Topology,
+    pub neighbours: usize,
+    pub phi1: f64, //cognitive acceleration
+    pub phi2: f64, //social acceleration
+    pub max_velocity: f64, //fraction of the width of the bounds
+}
+
+impl Default for PsoParameters {
+    fn default() -> Self {
+        Self {
+            topology: Topology::default(),
+            neighbours: 1,
+            phi1: 2.05,
+            phi2: 2.05,
+            max_velocity: 0.5,
+        }
+    }
+}
+
+impl PsoParameters {
+
+    //constriction coefficient of Clerc and Kennedy, defined for phi1 + phi2 > 4
+    pub fn constriction(&self) -> f64 {
+        let phi: f64 = self.phi1 + self.phi2;
+        if phi <= 4.0 {
+            return 1.0;
+        }
+        2.0/(2.0 - phi - (phi*phi - 4.0*phi).sqrt()).abs()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParticleSwarm {
+    parameters: PsoParameters,
+    bounds: Vec<Bound>,
+    minimization: bool,
+    swarm_size: usize,
+    max_iterations: usize,
+    termination: Termination,
+    hall_of_fame: Option<HallOfFameConfig>,
+    initialization: Initialization,
+    threads: Option<usize>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    positions: Vec<Chromosome>,
+    velocities: Vec<Vec<f64>>,
+    personal_best: Vec<Chromosome>,
+    history: History,
+}
+
+impl ParticleSwarm {
+
+    //the maximum velocity must not be negative, it is the range of the clamp of the velocities
+    pub fn from_metadata(metadata: &GA_Metadata, parameters: PsoParameters, bounds: Vec<Bound>, is_min: bool) -> Result<Self,()> {
+        if parameters.max_velocity.is_nan() || parameters.max_velocity < 0.0 {
+            println!("The max_velocity of the particle swarm must not be negative, found {}", parameters.max_velocity);
+            return Err(());
+        }
+        let seed: u64 = run_seed(metadata);
+        Ok(Self {
+            parameters,
+            bounds,
+            minimization: is_min,
+            swarm_size: metadata.population_size,
+            max_iterations: metadata.max_iterations,
+            termination: metadata.termination.clone(),
+            hall_of_fame: metadata.hall_of_fame.clone(),
+            initialization: metadata.initialization.clone(),
+            threads: metadata.threads,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            positions: vec![],
+            velocities: vec![],
+            personal_best: vec![],
+            history: History::default(),
+        })
+    }
+
+    //half the difference between the position and another random point of the box
+    fn initial_velocity(&mut self, position: &Chromosome) -> Vec<f64> {
+        self.bounds.iter()
+            .zip(position.values())
+            .map(|(b, x)| 0.5*(self.rng.gen_range(b.min..=b.max) - x))
+            .collect()
+    }
+
+    //index of the best personal best among the particles that inform `i`
+    fn informant(&self, i: usize, global: usize) -> usize {
+
+        if self.parameters.topology == Topology::Global {
+            return global;
+        }
+
+        let n: usize = self.personal_best.len();
+        let k: usize = self.parameters.neighbours.min(n/2);
+        (n + i - k..=n + i + k)
+            .map(|j| j % n)
+            .reduce(|best, j| if is_fitter(&self.personal_best[j], &self.personal_best[best], self.minimization) { j } else { best })
+            .unwrap_or(i)
+    }
+
+    fn move_particles(&mut self) {
+
+        let chi: f64 = self.parameters.constriction();
+        let global: usize = (0..self.personal_best.len())
+            .reduce(|best, j| if is_fitter(&self.personal_best[j], &self.personal_best[best], self.minimization) { j } else { best })
+            .unwrap_or(0);
+
+        for i in 0..self.positions.len() {
+
+            let informant: usize = self.informant(i, global);
+            let mut values: Vec<f64> = self.positions[i].get_values();
+
+            for (j, bound) in self.bounds.iter().enumerate() {
+
+                let x: f64 = values[j];
+                let cognitive: f64 = self.parameters.phi1*self.rng.gen_range(0.0..1.0)*(self.personal_best[i].values()[j] - x);
+                let social: f64 = self.parameters.phi2*self.rng.gen_range(0.0..1.0)*(self.personal_best[informant].values()[j] - x);
+
+                let v_max: f64 = self.parameters.max_velocity*(bound.max - bound.min);
+                let mut v: f64 = (chi*(self.velocities[i][j] + cognitive + social)).clamp(-v_max, v_max);
+
+                //a particle leaving the box stops at its border
+                if x + v < bound.min || x + v > bound.max {
+                    values[j] = bound.clamp(x + v);
+                    v = 0.0;
+                }
+                else {
+                    values[j] = x + v;
+                }
+                self.velocities[i][j] = v;
+            }
+            self.positions[i] = Chromosome::new(values);
+        }
+    }
+}
+
+impl<O: Objective> Optimizer<O> for ParticleSwarm {
+
+    fn bounds(&self) -> &[Bound] {
+        &self.bounds
+    }
+
+    fn history(&self) -> &History {
+        &self.history
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn optimize(&mut self, objective: &O, observer: &mut dyn Observer) -> Result<OptimizationResult,()> {
+
+        let start: Instant = Instant::now();
+
+        let pool: ThreadPool = thread_pool(self.threads)?;
+
+        self.positions = self.initialization.population(&self.bounds, self.swarm_size, &mut self.rng)?;
+        let positions: Vec<Chromosome> = self.positions.clone();
+        self.velocities = positions.iter().map(|p| self.initial_velocity(p)).collect();
+
+        let evaluations: usize = evaluate_population(&pool, &mut self.positions, objective);
+        self.personal_best = self.positions.clone();
+
+        let mut tracker: RunTracker = RunTracker::new(start, &self.positions, &self.bounds, evaluations, self.minimization, self.hall_of_fame.as_ref(), observer)?;
+
+        let stop_reason: StopReason = loop {
+
+            if let Some(reason) = tracker.next_iteration(&self.termination, self.max_iterations, observer) {
+                break reason;
+            }
+
+            self.move_particles();
+            tracker.evaluations += evaluate_population(&pool, &mut self.positions, objective);
+
+            for (best, position) in self.personal_best.iter_mut().zip(self.positions.iter()) {
+                if is_fitter(position, best, self.minimization) {
+                    *best = position.clone();
+                }
+            }
+
+            tracker.end_iteration(&self.positions, &self.bounds, observer);
+        };
+
+        let result: OptimizationResult = tracker.finish(stop_reason, observer);
+        self.history = result.history.clone();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::observer::ConsoleObserver;
+
+    type Quadratic = (fn(), fn(&mut (), &[f64]) -> f64);
+
+    #[test]
+    fn converges_to_the_minimum_of_a_quadratic() {
+        let objective: Quadratic = (|| (), |_, values| (values[0] - 1.0).powi(2) + (values[1] + 2.0).powi(2));
+        let metadata: GA_Metadata = GA_Metadata { population_size: 20, max_iterations: 150, seed: Some(1), threads: Some(1), ..Default::default() };
+
+        for topology in [Topology::Global, Topology::Ring] {
+            let bounds: Vec<Bound> = vec![Bound::new(String::from("x"), -5.0, 5.0), Bound::new(String::from("y"), -5.0, 5.0)];
+            let parameters: PsoParameters = PsoParameters { topology, ..Default::default() };
+            let result: OptimizationResult = ParticleSwarm::from_metadata(&metadata, parameters, bounds, true)
+                .unwrap()
+                .optimize(&objective, &mut ConsoleObserver)
+                .unwrap();
+            assert!(result.best.fitness < 1e-8);
+            assert!((result.best.values()[0] - 1.0).abs() < 1e-4 && (result.best.values()[1] + 2.0).abs() < 1e-4);
+        }
+    }
+}
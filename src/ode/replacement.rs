@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use super::ga::{sort_population, Chromosome};
+
+//how the offspring of a generation and the current population form the next population
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Replacement {
+    //the offspring replace the whole population, except for the `elitism` best parents
+    Generational,
+    //parents and `lambda` children compete, the best mu survive (default lambda = mu/2)
+    MuPlusLambda { lambda: Option<usize> },
+    //only the `lambda` children compete, apart from the elite (default lambda = mu)
+    MuCommaLambda { lambda: Option<usize> },
+    //`replacements` children replace the worst individuals of the population (default 2)
+    SteadyState { replacements: Option<usize> },
+}
+
+impl Default for Replacement {
+    fn default() -> Self {
+        Replacement::MuPlusLambda { lambda: None }
+    }
+}
+
+impl Replacement {
+
+    //number of children to be created in each generation for a population of size `mu`
+    pub fn offspring_count(&self, mu: usize, elitism: usize) -> usize {
+        let elitism: usize = elitism.min(mu);
+        match self {
+            Replacement::Generational => mu - elitism,
+            Replacement::MuPlusLambda { lambda } => lambda.unwrap_or(mu/2).max(1),
+            Replacement::MuCommaLambda { lambda } => lambda.unwrap_or(mu).max(mu - elitism),
+            Replacement::SteadyState { replacements } => replacements.unwrap_or(2).clamp(1, (mu - elitism).max(1)),
+        }
+    }
+
+    //returns the next population, sorted from the best to the worst individual, with the same size as `parents`
+    pub fn replace(&self, mut parents: Vec<Chromosome>, mut offspring: Vec<Chromosome>, elitism: usize, minimization: bool) -> Vec<Chromosome> {
+
+        let mu: usize = parents.len();
+        let elitism: usize = elitism.min(mu);
+
+        sort_population(&mut parents, minimization);
+        sort_population(&mut offspring, minimization);
+
+        let mut next: Vec<Chromosome> = match self {
+            Replacement::MuPlusLambda { .. } => {
+                parents.append(&mut offspring);
+                sort_population(&mut parents, minimization);
+                parents
+            },
+            Replacement::Generational | Replacement::MuCommaLambda { .. } => {
+                parents.truncate(elitism);
+                parents.append(&mut offspring);
+                sort_population(&mut parents, minimization);
+                parents
+            },
+            Replacement::SteadyState { .. } => {
+                let count: usize = offspring.len().min(mu - elitism);
+                parents.truncate(mu - count);
+                offspring.truncate(count);
+                parents.append(&mut offspring);
+                sort_population(&mut parents, minimization);
+                parents
+            },
+        };
+
+        next.truncate(mu);
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn population(fitness: &[f64]) -> Vec<Chromosome> {
+        fitness
+            .iter()
+            .map(|f| {
+                let mut c: Chromosome = Chromosome::new(vec![*f]);
+                c.fitness = *f;
+                c
+            })
+            .collect()
+    }
+
+    fn fitness(population: &[Chromosome]) -> Vec<f64> {
+        population.iter().map(|c| c.fitness).collect()
+    }
+
+    #[test]
+    fn mu_plus_lambda_minimization() {
+        let next = Replacement::MuPlusLambda { lambda: None }
+                    .replace(population(&[4.0, 1.0, 3.0]), population(&[0.5, 5.0]), 0, true);
+        assert_eq!(fitness(&next), vec![0.5, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn mu_plus_lambda_maximization() {
+        let next = Replacement::MuPlusLambda { lambda: None }
+                    .replace(population(&[4.0, 1.0, 3.0]), population(&[0.5, 5.0]), 0, false);
+        assert_eq!(fitness(&next), vec![5.0, 4.0, 3.0]);
+    }
+
+    #[test]
+    fn mu_comma_lambda_keeps_elite() {
+        let parents = population(&[4.0, 1.0, 3.0]);
+        let offspring = population(&[6.0, 2.0, 7.0]);
+
+        let next = Replacement::MuCommaLambda { lambda: None }.replace(parents.clone(), offspring.clone(), 1, true);
+        assert_eq!(fitness(&next), vec![1.0, 2.0, 6.0]);
+
+        let next = Replacement::MuCommaLambda { lambda: None }.replace(parents, offspring, 1, false);
+        assert_eq!(fitness(&next), vec![7.0, 6.0, 4.0]);
+    }
+
+    #[test]
+    fn generational_without_elitism_loses_parents() {
+        let next = Replacement::Generational.replace(population(&[1.0, 2.0]), population(&[8.0, 9.0]), 0, true);
+        assert_eq!(fitness(&next), vec![8.0, 9.0]);
+
+        let next = Replacement::Generational.replace(population(&[8.0, 9.0]), population(&[1.0, 2.0]), 0, false);
+        assert_eq!(fitness(&next), vec![2.0, 1.0]);
+    }
+
+    #[test]
+    fn steady_state_replaces_worst() {
+        let next = Replacement::SteadyState { replacements: Some(1) }
+                    .replace(population(&[1.0, 2.0, 3.0]), population(&[10.0]), 1, true);
+        assert_eq!(fitness(&next), vec![1.0, 2.0, 10.0]);
+
+        let next = Replacement::SteadyState { replacements: Some(1) }
+                    .replace(population(&[1.0, 2.0, 3.0]), population(&[0.0]), 1, false);
+        assert_eq!(fitness(&next), vec![3.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn small_populations() {
+        let replacement = Replacement::MuCommaLambda { lambda: None };
+        assert_eq!(replacement.offspring_count(2, 5), 2);
+        assert_eq!(Replacement::Generational.offspring_count(3, 5), 0);
+
+        let next = replacement.replace(population(&[2.0, 1.0]), population(&[3.0]), 5, true);
+        assert_eq!(fitness(&next), vec![1.0, 2.0]);
+    }
+}
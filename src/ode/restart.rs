@@ -0,0 +1,55 @@
+use rand::Rng;
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+
+use super::ga::Chromosome;
+use super::ga_json::Bound;
+use super::initialization::{sample, Sampling};
+use super::objective::{evaluate_population, Objective};
+
+//samples again part of the population of the GA when its diversity falls below a threshold
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub min_diversity: f64, //mean normalized pairwise distance of the population
+    pub fraction: f64, //fraction of the population sampled again, 1 is a total restart
+    pub keep: usize, //best individuals that are always kept, besides the hall of fame (or the best individual of the run)
+    pub sampling: Sampling, //how the new individuals are spread over the bounds
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            min_diversity: 0.01,
+            fraction: 0.5,
+            keep: 1,
+            sampling: Sampling::default(),
+        }
+    }
+}
+
+impl RestartPolicy {
+
+    //replaces the worst individuals of the sorted population by new evaluated ones, the `elite` individuals
+    //are put back in the population if they were lost, returns the number of new individuals and of evaluations
+    pub fn reseed<O: Objective, R: Rng + ?Sized>(&self, pool: &ThreadPool, population: &mut Vec<Chromosome>, elite: &[Chromosome],
+                bounds: &[Bound], objective: &O, rng: &mut R) -> (usize, usize) {
+
+        let size: usize = population.len();
+        let count: usize = ((self.fraction.clamp(0.0, 1.0)*size as f64).round() as usize).min(size - self.keep.min(size));
+        population.truncate(size - count);
+
+        for individual in elite.iter() {
+            if !population.iter().any(|c| c.values() == individual.values()) {
+                population.insert(0, individual.clone());
+            }
+        }
+        population.truncate(size);
+
+        let mut reseeded: Vec<Chromosome> = sample(self.sampling, bounds, size - population.len(), rng);
+        let evaluations: usize = evaluate_population(pool, &mut reseeded, objective);
+        let count: usize = reseeded.len();
+        population.append(&mut reseeded);
+        (count, evaluations)
+    }
+}
@@ -0,0 +1,258 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use super::ga::{fitness_order, is_better, is_fitter, Chromosome};
+
+//strategy used to choose the parents of each generation
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Selection {
+    //fitness-proportional roulette
+    #[default]
+    Roulette,
+    //best of `size` individuals drawn at random
+    Tournament { size: usize },
+    //linear ranking, `pressure` is the expected number of copies of the best individual (between 1 and 2)
+    Rank { pressure: f64 },
+    //roulette with equally spaced pointers, a single spin for the whole mating pool
+    StochasticUniversalSampling,
+}
+
+impl Selection {
+
+    //returns the indexes of `n` individuals of the population chosen to be parents
+    pub fn select<R: Rng + ?Sized>(&self, population: &[Chromosome], n: usize, minimization: bool, rng: &mut R) -> Vec<usize> {
+
+        if population.is_empty() {
+            return vec![];
+        }
+
+        match self {
+            Selection::Roulette => {
+                let weights: Vec<f64> = fitness_weights(population, minimization);
+                (0..n).map(|_| spin(&weights, rng)).collect()
+            },
+            Selection::Tournament { size } => {
+                (0..n).map(|_| tournament(population, (*size).max(1), minimization, rng)).collect()
+            },
+            Selection::Rank { pressure } => {
+                let weights: Vec<f64> = rank_weights(population, *pressure, minimization);
+                (0..n).map(|_| spin(&weights, rng)).collect()
+            },
+            Selection::StochasticUniversalSampling => {
+                let weights: Vec<f64> = fitness_weights(population, minimization);
+                universal_sampling(&weights, n, rng)
+            },
+        }
+    }
+
+    //`n` pairs of parents, the two parents of a pair are different individuals if the population has more than one,
+    //the second one is selected again when it is equal to the first and drawn uniformly after a few attempts
+    pub fn select_pairs<R: Rng + ?Sized>(&self, population: &[Chromosome], n: usize, minimization: bool, rng: &mut R) -> Vec<(usize,usize)> {
+
+        let selected: Vec<usize> = self.select(population, 2*n, minimization, rng);
+        let mut pairs: Vec<(usize,usize)> = Vec::with_capacity(n);
+
+        for pair in selected.chunks_exact(2) {
+            let (first, mut second) = (pair[0], pair[1]);
+            let mut attempts: usize = 0;
+            while second == first && population.len() > 1 {
+                attempts += 1;
+                second = if attempts <= 10 {
+                    self.select(population, 1, minimization, rng)[0]
+                }
+                else {
+                    (first + rng.gen_range(1..population.len())) % population.len()
+                };
+            }
+            pairs.push((first, second));
+        }
+        pairs
+    }
+}
+
+//weights proportional to the distance to the worst individual, so that they are valid for minimization and maximization
+//the infeasible individuals are not chosen if there is a feasible one, otherwise the weights come from the violation
+fn fitness_weights(population: &[Chromosome], minimization: bool) -> Vec<f64> {
+
+    let feasible: bool = population.iter().any(|c| c.violation <= 0.0);
+    let worst: f64 = if feasible {
+        population
+            .iter()
+            .filter(|c| c.violation <= 0.0)
+            .map(|c| c.fitness)
+            .reduce(|w, f| if is_better(w, f, minimization) { f } else { w })
+            .unwrap_or(0.0)
+    }
+    else {
+        population.iter().map(|c| c.violation).filter(|v| v.is_finite()).fold(0.0, f64::max)
+    };
+
+    let weights: Vec<f64> = population
+                        .iter()
+                        .map(|c| match (feasible, c.violation <= 0.0) {
+                            (true, true) => (c.fitness - worst).abs(),
+                            (true, false) => 0.0,
+                            (false, _) => worst - c.violation,
+                        })
+                        .map(|w| if w.is_finite() { w } else { 0.0 })
+                        .collect();
+
+    //every individual has the same fitness: uniform choice
+    if weights.iter().sum::<f64>() <= 0.0 {
+        return vec![1.0; population.len()];
+    }
+    weights
+}
+
+fn rank_weights(population: &[Chromosome], pressure: f64, minimization: bool) -> Vec<f64> {
+
+    let s: f64 = pressure.clamp(1.0, 2.0);
+    let n: usize = population.len();
+    if n == 1 {
+        return vec![1.0];
+    }
+
+    //indexes ordered from the worst to the best individual
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| fitness_order(&population[b], &population[a], minimization));
+
+    let mut weights: Vec<f64> = vec![0.0; n];
+    for (rank, &index) in order.iter().enumerate() {
+        weights[index] = 2.0 - s + 2.0*(s - 1.0)*(rank as f64)/((n - 1) as f64);
+    }
+    weights
+}
+
+fn spin<R: Rng + ?Sized>(weights: &[f64], rng: &mut R) -> usize {
+
+    let total: f64 = weights.iter().sum();
+    let mut pointer: f64 = rng.gen_range(0.0..total);
+
+    for (i, w) in weights.iter().enumerate() {
+        pointer -= w;
+        if pointer < 0.0 {
+            return i;
+        }
+    }
+    weights.len() - 1
+}
+
+fn tournament<R: Rng + ?Sized>(population: &[Chromosome], size: usize, minimization: bool, rng: &mut R) -> usize {
+
+    let mut winner: usize = rng.gen_range(0..population.len());
+
+    for _ in 1..size {
+        let challenger: usize = rng.gen_range(0..population.len());
+        if is_fitter(&population[challenger], &population[winner], minimization) {
+            winner = challenger;
+        }
+    }
+    winner
+}
+
+fn universal_sampling<R: Rng + ?Sized>(weights: &[f64], n: usize, rng: &mut R) -> Vec<usize> {
+
+    if n == 0 {
+        return vec![];
+    }
+
+    let total: f64 = weights.iter().sum();
+    let distance: f64 = total / n as f64;
+    let start: f64 = rng.gen_range(0.0..distance);
+
+    let mut selected: Vec<usize> = Vec::with_capacity(n);
+    let mut cumulative: f64 = weights[0];
+    let mut i: usize = 0;
+
+    for k in 0..n {
+        let pointer: f64 = start + k as f64 * distance;
+        while cumulative <= pointer && i < weights.len() - 1 {
+            i += 1;
+            cumulative += weights[i];
+        }
+        selected.push(i);
+    }
+    //the pointers are sorted, shuffle them so that consecutive parents are not neighbours
+    selected.shuffle(rng);
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn population(fitness: &[f64]) -> Vec<Chromosome> {
+        fitness
+            .iter()
+            .map(|f| {
+                let mut c: Chromosome = Chromosome::new(vec![*f]);
+                c.set_fitness(*f);
+                c
+            })
+            .collect()
+    }
+
+    fn counts(selected: &[usize], n: usize) -> Vec<usize> {
+        (0..n).map(|i| selected.iter().filter(|s| **s == i).count()).collect()
+    }
+
+    #[test]
+    fn large_tournament_chooses_the_best() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(1);
+        let population: Vec<Chromosome> = population(&[3.0, 1.0, 4.0, 2.0]);
+        let tournament: Selection = Selection::Tournament { size: 100 };
+        assert!(tournament.select(&population, 20, true, &mut rng).iter().all(|i| *i == 1));
+        assert!(tournament.select(&population, 20, false, &mut rng).iter().all(|i| *i == 2));
+    }
+
+    #[test]
+    fn tournament_of_one_is_uniform() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(2);
+        let population: Vec<Chromosome> = population(&[3.0, 1.0, 4.0, 2.0]);
+        let selected: Vec<usize> = Selection::Tournament { size: 1 }.select(&population, 4000, true, &mut rng);
+        assert!(counts(&selected, 4).iter().all(|c| (800..1200).contains(c)));
+    }
+
+    #[test]
+    fn rank_weights_are_linear_in_the_rank() {
+        let population: Vec<Chromosome> = population(&[3.0, 1.0, 2.0]);
+        assert_eq!(rank_weights(&population, 2.0, true), vec![0.0, 2.0, 1.0]);
+        assert_eq!(rank_weights(&population, 2.0, false), vec![2.0, 0.0, 1.0]);
+        assert_eq!(rank_weights(&population, 1.0, true), vec![1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn universal_sampling_gives_the_expected_number_of_copies() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(3);
+        for _ in 0..20 {
+            let selected: Vec<usize> = universal_sampling(&[1.0, 1.0, 2.0, 0.0], 8, &mut rng);
+            assert_eq!(counts(&selected, 4), vec![2, 2, 4, 0]);
+        }
+    }
+
+    #[test]
+    fn roulette_never_chooses_the_worst_or_an_infeasible_individual() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(4);
+        let mut population: Vec<Chromosome> = population(&[3.0, 1.0, 2.0, 0.5]);
+        population[3].violation = 1.0;
+        let selected: Vec<usize> = Selection::Roulette.select(&population, 200, true, &mut rng);
+        assert_eq!(counts(&selected, 4)[0], 0);
+        assert_eq!(counts(&selected, 4)[3], 0);
+    }
+
+    #[test]
+    fn pairs_have_distinct_parents() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(5);
+        let population: Vec<Chromosome> = population(&[3.0, 1.0, 4.0, 2.0]);
+        for selection in [Selection::Tournament { size: 100 }, Selection::Rank { pressure: 2.0 }, Selection::StochasticUniversalSampling] {
+            let pairs: Vec<(usize,usize)> = selection.select_pairs(&population, 50, true, &mut rng);
+            assert_eq!(pairs.len(), 50);
+            assert!(pairs.iter().all(|(a, b)| a != b));
+        }
+        assert_eq!(Selection::Roulette.select_pairs(&population[..1], 2, true, &mut rng), vec![(0, 0), (0, 0)]);
+    }
+}
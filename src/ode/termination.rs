@@ -0,0 +1,113 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::ga::{is_better, is_fitter, Chromosome};
+
+//stopping rules checked at the end of every generation, the first one satisfied ends the run
+//max_iterations is always applied
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Termination {
+    pub target_fitness: Option<f64>, //stop when the best fitness is at least as good as this value and it is feasible
+    pub stagnation: Option<usize>, //number of generations without improvement of the best fitness
+    pub min_diversity: Option<f64>, //mean normalized pairwise distance of the population
+    pub max_evaluations: Option<usize>, //checked at the end of each generation, so it can be exceeded by one generation
+    pub time_limit: Option<f64>, //seconds
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum StopReason {
+    MaxGenerations,
+    TargetFitness,
+    Stagnation,
+    LowDiversity,
+    MaxEvaluations,
+    TimeLimit,
+    Cancelled,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason: &str = match self {
+            StopReason::MaxGenerations => "maximum number of generations reached",
+            StopReason::TargetFitness => "target fitness reached",
+            StopReason::Stagnation => "no improvement of the best fitness",
+            StopReason::LowDiversity => "population diversity below the threshold",
+            StopReason::MaxEvaluations => "maximum number of evaluations reached",
+            StopReason::TimeLimit => "time limit reached",
+            StopReason::Cancelled => "cancelled by an observer",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+//state of the run used to check the stopping rules
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    pub generation: usize,
+    pub evaluations: usize,
+    pub best: Chromosome,
+    pub diversity: f64,
+    pub elapsed: Duration,
+    pub generations_without_improvement: usize,
+}
+
+impl Progress {
+
+    pub fn new(best: Chromosome) -> Self {
+        Self {
+            best,
+            ..Default::default()
+        }
+    }
+
+    //updates the best individual found so far and the stagnation counter
+    pub fn update_best(&mut self, best: &Chromosome, minimization: bool) {
+        if is_fitter(best, &self.best, minimization) || self.best.fitness.is_nan() {
+            self.best = best.clone();
+            self.generations_without_improvement = 0;
+        }
+        else {
+            self.generations_without_improvement += 1;
+        }
+    }
+}
+
+impl Termination {
+
+    pub fn check(&self, progress: &Progress, max_generations: usize, minimization: bool) -> Option<StopReason> {
+
+        if let Some(target) = self.target_fitness {
+            let fitness: f64 = progress.best.fitness;
+            if progress.best.violation <= 0.0 && (fitness == target || is_better(fitness, target, minimization)) {
+                return Some(StopReason::TargetFitness);
+            }
+        }
+        if let Some(generations) = self.stagnation {
+            if progress.generations_without_improvement >= generations {
+                return Some(StopReason::Stagnation);
+            }
+        }
+        if let Some(diversity) = self.min_diversity {
+            if progress.diversity < diversity {
+                return Some(StopReason::LowDiversity);
+            }
+        }
+        if let Some(evaluations) = self.max_evaluations {
+            if progress.evaluations >= evaluations {
+                return Some(StopReason::MaxEvaluations);
+            }
+        }
+        if let Some(seconds) = self.time_limit {
+            if progress.elapsed.as_secs_f64() >= seconds {
+                return Some(StopReason::TimeLimit);
+            }
+        }
+        if progress.generation >= max_generations {
+            return Some(StopReason::MaxGenerations);
+        }
+        None
+    }
+}